@@ -0,0 +1,78 @@
+use crate::{prepare_gssv, Options, SolverError, SuperLuScalar};
+use ndarray::Array1;
+use sprs::CsMat;
+
+type Solution<T> = Result<Vec<Array1<T>>, SolverError>;
+
+/// Solves many independent systems `A x = b` in parallel.
+///
+/// The systems run on a bounded set of worker threads: rayon's global pool
+/// with the `rayon` feature, otherwise the blocking pool shared with
+/// [`crate::solve_super_lu_async`]. The results are in input order, and an
+/// error only affects its own system.
+///
+/// Called from a task on the blocking pool, e.g. while another solve is
+/// running there, the systems are solved one after another on the calling
+/// thread, since waiting for the pool from inside it could deadlock.
+pub fn solve_batch<T: SuperLuScalar>(
+    systems: impl IntoIterator<Item = (CsMat<T>, Vec<Array1<T>>)>,
+    options: &mut Options,
+) -> Vec<Solution<T>> {
+    let systems: Vec<_> = systems.into_iter().collect();
+    solve_all(systems, options)
+}
+
+fn solve_one<T: SuperLuScalar>(a: CsMat<T>, b: Vec<Array1<T>>, options: &Options) -> Solution<T> {
+    prepare_gssv(a, &b, options).and_then(|task| task())
+}
+
+#[cfg(feature = "rayon")]
+fn solve_all<T: SuperLuScalar>(
+    systems: Vec<(CsMat<T>, Vec<Array1<T>>)>,
+    options: &Options,
+) -> Vec<Solution<T>> {
+    use rayon::prelude::*;
+    systems
+        .into_par_iter()
+        .map(|(a, b)| solve_one(a, b, options))
+        .collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn solve_all<T: SuperLuScalar>(
+    systems: Vec<(CsMat<T>, Vec<Array1<T>>)>,
+    options: &Options,
+) -> Vec<Solution<T>> {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::{mpsc, Arc};
+
+    if crate::pool::on_worker() {
+        return systems
+            .into_iter()
+            .map(|(a, b)| solve_one(a, b, options))
+            .collect();
+    }
+
+    let count = systems.len();
+    let options = Arc::new(options.clone());
+    let (sender, receiver) = mpsc::channel();
+    for (index, (a, b)) in systems.into_iter().enumerate() {
+        let sender = sender.clone();
+        let options = Arc::clone(&options);
+        crate::pool::spawn(move || {
+            let solution = panic::catch_unwind(AssertUnwindSafe(|| solve_one(a, b, &options)))
+                .unwrap_or(Err(SolverError::Unsolvable));
+            let _ = sender.send((index, solution));
+        });
+    }
+    drop(sender);
+
+    let mut solutions: Vec<Option<Solution<T>>> = (0..count).map(|_| None).collect();
+    for (index, solution) in receiver {
+        solutions[index] = Some(solution);
+    }
+    solutions
+        .into_iter()
+        .map(|solution| solution.unwrap_or(Err(SolverError::Unsolvable)))
+        .collect()
+}
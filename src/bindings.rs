@@ -0,0 +1,527 @@
+//! SuperLU declarations that `superlu-sys` does not provide, or provides with
+//! a layout that does not match the bundled SuperLU 5.2 sources.
+#![allow(non_camel_case_types, non_snake_case, clippy::upper_case_acronyms)]
+
+use libc::{c_char, c_double, c_float, c_int, c_void};
+use num_complex::{Complex32, Complex64};
+use superlu_sys::{
+    superlu_options_t, trans_t, Dtype_t, Mtype_t, Stype_t, SuperLUStat_t, SuperMatrix,
+};
+
+/// `Complex<T>` is `repr(C)` with the real part first, like SuperLU's structs.
+pub type complex = Complex32;
+pub type doublecomplex = Complex64;
+
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub enum LU_space_t {
+    SYSTEM,
+    USER,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct ExpHeader {
+    pub size: c_int,
+    pub mem: *mut c_void,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct LU_stack_t {
+    pub size: c_int,
+    pub used: c_int,
+    pub top1: c_int,
+    pub top2: c_int,
+    pub array: *mut c_void,
+}
+
+/// Full layout of `GlobalLU_t`. The struct exported by `superlu-sys` stops
+/// after `num_expansions`, which makes `dgstrf` write past its end.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct GlobalLU_t {
+    pub xsup: *mut c_int,
+    pub supno: *mut c_int,
+    pub lsub: *mut c_int,
+    pub xlsub: *mut c_int,
+    pub lusup: *mut c_void,
+    pub xlusup: *mut c_int,
+    pub ucol: *mut c_void,
+    pub usub: *mut c_int,
+    pub xusub: *mut c_int,
+    pub nzlmax: c_int,
+    pub nzumax: c_int,
+    pub nzlumax: c_int,
+    pub n: c_int,
+    pub MemModel: LU_space_t,
+    pub num_expansions: c_int,
+    pub expanders: *mut ExpHeader,
+    pub stack: LU_stack_t,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct NRformat {
+    pub nnz: c_int,
+    pub nzval: *mut c_void,
+    pub colind: *mut c_int,
+    pub rowptr: *mut c_int,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct NCPformat {
+    pub nnz: c_int,
+    pub nzval: *mut c_void,
+    pub rowind: *mut c_int,
+    pub colbeg: *mut c_int,
+    pub colend: *mut c_int,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct mem_usage_t {
+    pub for_lu: c_float,
+    pub total_needed: c_float,
+}
+
+extern "C" {
+    pub fn get_perm_c(ispec: c_int, A: *mut SuperMatrix, perm_c: *mut c_int);
+
+    pub fn dgstrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn dgssvx(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        etree: *mut c_int,
+        equed: *mut c_char,
+        R: *mut c_double,
+        C: *mut c_double,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        work: *mut c_void,
+        lwork: c_int,
+        B: *mut SuperMatrix,
+        X: *mut SuperMatrix,
+        recip_pivot_growth: *mut c_double,
+        rcond: *mut c_double,
+        ferr: *mut c_double,
+        berr: *mut c_double,
+        Glu: *mut GlobalLU_t,
+        mem_usage: *mut mem_usage_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn sgssv(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn sgstrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn sgstrs(
+        trans: trans_t,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn sCreate_CompCol_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut c_float,
+        rowind: *mut c_int,
+        colptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn sCreate_Dense_Matrix(
+        X: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        x: *mut c_float,
+        ldx: c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn floatMalloc(n: c_int) -> *mut c_float;
+
+    pub fn zgssv(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn zgstrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn zgstrs(
+        trans: trans_t,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn zCreate_CompCol_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut doublecomplex,
+        rowind: *mut c_int,
+        colptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn zCreate_Dense_Matrix(
+        X: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        x: *mut doublecomplex,
+        ldx: c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn doublecomplexMalloc(n: c_int) -> *mut doublecomplex;
+
+    pub fn sgssvx(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        etree: *mut c_int,
+        equed: *mut c_char,
+        R: *mut c_float,
+        C: *mut c_float,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        work: *mut c_void,
+        lwork: c_int,
+        B: *mut SuperMatrix,
+        X: *mut SuperMatrix,
+        recip_pivot_growth: *mut c_float,
+        rcond: *mut c_float,
+        ferr: *mut c_float,
+        berr: *mut c_float,
+        Glu: *mut GlobalLU_t,
+        mem_usage: *mut mem_usage_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cgssv(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cgstrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cgstrs(
+        trans: trans_t,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        B: *mut SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cgssvx(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        etree: *mut c_int,
+        equed: *mut c_char,
+        R: *mut c_float,
+        C: *mut c_float,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        work: *mut c_void,
+        lwork: c_int,
+        B: *mut SuperMatrix,
+        X: *mut SuperMatrix,
+        recip_pivot_growth: *mut c_float,
+        rcond: *mut c_float,
+        ferr: *mut c_float,
+        berr: *mut c_float,
+        Glu: *mut GlobalLU_t,
+        mem_usage: *mut mem_usage_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cCreate_CompCol_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut complex,
+        rowind: *mut c_int,
+        colptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn cCreate_Dense_Matrix(
+        X: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        x: *mut complex,
+        ldx: c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn complexMalloc(n: c_int) -> *mut complex;
+
+    pub fn zgssvx(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        etree: *mut c_int,
+        equed: *mut c_char,
+        R: *mut c_double,
+        C: *mut c_double,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        work: *mut c_void,
+        lwork: c_int,
+        B: *mut SuperMatrix,
+        X: *mut SuperMatrix,
+        recip_pivot_growth: *mut c_double,
+        rcond: *mut c_double,
+        ferr: *mut c_double,
+        berr: *mut c_double,
+        Glu: *mut GlobalLU_t,
+        mem_usage: *mut mem_usage_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn sCreate_CompRow_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut c_float,
+        colind: *mut c_int,
+        rowptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn dCreate_CompRow_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut c_double,
+        colind: *mut c_int,
+        rowptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn cCreate_CompRow_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut complex,
+        colind: *mut c_int,
+        rowptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn zCreate_CompRow_Matrix(
+        A: *mut SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut doublecomplex,
+        colind: *mut c_int,
+        rowptr: *mut c_int,
+        stype: Stype_t,
+        dtype: Dtype_t,
+        mtype: Mtype_t,
+    );
+
+    pub fn sgsitrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn dgsitrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn cgsitrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    pub fn zgsitrf(
+        options: *mut superlu_options_t,
+        A: *mut SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        L: *mut SuperMatrix,
+        U: *mut SuperMatrix,
+        Glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+}
@@ -0,0 +1,93 @@
+use crate::{
+    check_system_rows, gssv_in_place, run_with_timeout, transposed, Options, SolverError,
+    SuperLuScalar, SuperMatrix,
+};
+use libc::c_int;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, ArrayViewMut2, Axis, ShapeBuilder};
+use sprs::CsMat;
+use std::iter;
+use std::time::Duration;
+use superlu_sys as ffi;
+
+/// Solves `A x = b` for a single right-hand side.
+pub fn solve_super_lu_vector<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: ArrayView1<T>,
+    timeout: Option<Duration>,
+    options: &mut Options,
+) -> Result<Array1<T>, SolverError> {
+    let x = solve_super_lu_array(a, b.insert_axis(Axis(1)), timeout, options)?;
+    Ok(x.index_axis_move(Axis(1), 0))
+}
+
+/// Solves `A X = B` for the columns of `b`, which may have any layout.
+///
+/// `b` is copied once, into the column-major array that SuperLU overwrites
+/// with the solution and that is returned. Use [`solve_super_lu_in_place`] to
+/// avoid the copy.
+pub fn solve_super_lu_array<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: ArrayView2<T>,
+    timeout: Option<Duration>,
+    options: &mut Options,
+) -> Result<Array2<T>, SolverError> {
+    check_system_rows(a.view(), iter::once(b.nrows()))?;
+    let perm_c_init = options.initial_perm_c(a.cols())?;
+
+    let mut a_mat = SuperMatrix::from_cs_mat(transposed(a, options.ffi.Trans));
+    let mut x = column_major_copy(b);
+    let mut options = options.ffi;
+
+    run_with_timeout(timeout, move || {
+        let b_mat = SuperMatrix::from_ndarray_view_mut(x.view_mut()).expect("column-major copy");
+        gssv_dense::<T>(&mut a_mat, b_mat, &perm_c_init, &mut options)?;
+        Ok(x)
+    })
+}
+
+/// Solves `A X = B`, overwriting `b` with the solution.
+///
+/// A column-major `b`, such as a Fortran-ordered array or a slice of its
+/// rows, is handed to SuperLU without copying. Other layouts are solved in a
+/// column-major copy. Since `b` is borrowed, the solve runs on the calling
+/// thread and cannot time out.
+pub fn solve_super_lu_in_place<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: &mut ArrayViewMut2<T>,
+    options: &mut Options,
+) -> Result<(), SolverError> {
+    check_system_rows(a.view(), iter::once(b.nrows()))?;
+    let perm_c_init = options.initial_perm_c(a.cols())?;
+
+    let mut a_mat = SuperMatrix::from_cs_mat(transposed(a, options.ffi.Trans));
+    let mut options = options.ffi;
+
+    if let Some(b_mat) = SuperMatrix::from_ndarray_view_mut(b.view_mut()) {
+        return gssv_dense::<T>(&mut a_mat, b_mat, &perm_c_init, &mut options);
+    }
+    let mut x = column_major_copy(b.view());
+    let b_mat = SuperMatrix::from_ndarray_view_mut(x.view_mut()).expect("column-major copy");
+    gssv_dense::<T>(&mut a_mat, b_mat, &perm_c_init, &mut options)?;
+    b.assign(&x);
+    Ok(())
+}
+
+/// Copies `b` into a new Fortran-ordered array.
+pub(crate) fn column_major_copy<T: SuperLuScalar>(b: ArrayView2<T>) -> Array2<T> {
+    let mut x = Array2::zeros(b.raw_dim().f());
+    x.assign(&b);
+    x
+}
+
+fn gssv_dense<T: SuperLuScalar>(
+    a_mat: &mut SuperMatrix,
+    mut b_mat: SuperMatrix,
+    perm_c_init: &[c_int],
+    options: &mut ffi::superlu_options_t,
+) -> Result<(), SolverError> {
+    let n = a_mat.ncols();
+    match gssv_in_place::<T>(a_mat, &mut b_mat, perm_c_init, options) {
+        0 => Ok(()),
+        info => Err(SolverError::from_info(info, n)),
+    }
+}
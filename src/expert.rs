@@ -0,0 +1,192 @@
+use crate::bindings;
+use crate::{
+    check_system, columns_from_dense_data, run_with_timeout, vec_of_array1_to_array2, Options,
+    SolverError, SuperLuScalar, SuperMatrix,
+};
+use libc::{c_char, c_int};
+use ndarray::Array1;
+use sprs::CsMat;
+use std::mem;
+use std::time::Duration;
+use superlu_sys as ffi;
+
+/// Scaling applied to `A` before the factorization.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Equilibration {
+    None,
+    /// `A` was replaced by `diag(R) * A`.
+    Row,
+    /// `A` was replaced by `A * diag(C)`.
+    Column,
+    /// `A` was replaced by `diag(R) * A * diag(C)`.
+    Both,
+}
+
+impl Equilibration {
+    fn from_equed(equed: c_char) -> Self {
+        match equed as u8 {
+            b'R' => Equilibration::Row,
+            b'C' => Equilibration::Column,
+            b'B' => Equilibration::Both,
+            _ => Equilibration::None,
+        }
+    }
+
+    fn transposed(self) -> Self {
+        match self {
+            Equilibration::Row => Equilibration::Column,
+            Equilibration::Column => Equilibration::Row,
+            other => other,
+        }
+    }
+}
+
+/// Solution of the expert driver together with its accuracy estimates.
+#[derive(Clone, Debug)]
+pub struct SolveReport<T: SuperLuScalar = f64> {
+    pub solution: Vec<Array1<T>>,
+    /// Estimate of the reciprocal condition number of `A`. Values below the
+    /// machine precision mean the solution may have no correct digits.
+    pub rcond: T::Real,
+    /// Reciprocal pivot growth `max |A_ij| / max |U_ij|`. Values much smaller
+    /// than one indicate an unstable factorization.
+    pub recip_pivot_growth: T::Real,
+    /// Estimated forward error bound for each solution column. Only computed
+    /// with iterative refinement, otherwise SuperLU reports `1.0`.
+    pub ferr: Vec<T::Real>,
+    /// Componentwise relative backward error of each solution column. Only
+    /// computed with iterative refinement, otherwise SuperLU reports `1.0`.
+    pub berr: Vec<T::Real>,
+    pub equed: Equilibration,
+    /// Row scale factors `R`, all ones unless rows were equilibrated.
+    pub row_scale: Vec<T::Real>,
+    /// Column scale factors `C`, all ones unless columns were equilibrated.
+    pub col_scale: Vec<T::Real>,
+}
+
+/// Solves `A x = b` with the expert driver `?gssvx`.
+///
+/// Besides the solution, the returned report carries the condition estimate,
+/// the pivot growth, error bounds and the equilibration that was applied.
+/// The options' `Equil`, `ColPerm`, `Trans`, `IterRefine` and pivoting
+/// settings are honored; the matrix is always factored from scratch. As for
+/// [`crate::solve_super_lu`], a worker thread is only used with a timeout.
+pub fn solve_expert<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: &Vec<Array1<T>>,
+    timeout: Option<Duration>,
+    options: &mut Options,
+) -> Result<SolveReport<T>, SolverError> {
+    check_system(a.view(), b)?;
+    let n = a.cols();
+    let nrhs = b.len();
+
+    let mut perm_c = options.initial_perm_c(n)?;
+
+    // For CSR input `?gssvx` factors and scales the transpose of `A`. It then
+    // solves with `A^T` for every `Trans` other than `NOTRANS`, which loses
+    // the conjugation, so `CONJ` needs the CSC form.
+    let a = match options.ffi.Trans {
+        ffi::trans_t::CONJ if a.is_csr() => a.to_csc(),
+        _ => a,
+    };
+    let transposed = a.is_csr();
+    let mut a_mat = SuperMatrix::from_cs_mat(a);
+    let mut b_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    // Overwritten with the solution.
+    let mut x_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    let mut options = options.ffi;
+    options.Fact = ffi::fact_t::DOFACT;
+    options.PivotGrowth = ffi::yes_no_t::YES;
+    options.ConditionNumber = ffi::yes_no_t::YES;
+
+    run_with_timeout(timeout, move || unsafe {
+        let mut perm_r: Vec<c_int> = vec![0; n];
+        let mut etree: Vec<c_int> = vec![0; n];
+        let mut equed = b'N' as c_char;
+        let one = T::Real::from(1.0);
+        let zero = T::Real::from(0.0);
+        let mut row_scale = vec![one; n];
+        let mut col_scale = vec![one; n];
+        let mut ferr = vec![zero; nrhs];
+        let mut berr = vec![zero; nrhs];
+        let mut recip_pivot_growth = zero;
+        let mut rcond = zero;
+
+        let mut l_mat: ffi::SuperMatrix = mem::zeroed();
+        let mut u_mat: ffi::SuperMatrix = mem::zeroed();
+        let mut glu: bindings::GlobalLU_t = mem::zeroed();
+        let mut mem_usage: bindings::mem_usage_t = mem::zeroed();
+
+        let mut stat: ffi::SuperLUStat_t = mem::zeroed();
+        ffi::StatInit(&mut stat);
+
+        let mut info = 0;
+        T::gssvx(
+            &mut options,
+            a_mat.raw_mut(),
+            perm_c.as_mut_ptr(),
+            perm_r.as_mut_ptr(),
+            etree.as_mut_ptr(),
+            &mut equed,
+            row_scale.as_mut_ptr(),
+            col_scale.as_mut_ptr(),
+            &mut l_mat,
+            &mut u_mat,
+            std::ptr::null_mut(),
+            0,
+            b_mat.raw_mut(),
+            x_mat.raw_mut(),
+            &mut recip_pivot_growth,
+            &mut rcond,
+            ferr.as_mut_ptr(),
+            berr.as_mut_ptr(),
+            &mut glu,
+            &mut mem_usage,
+            &mut stat,
+            &mut info,
+        );
+
+        // L and U exist once the factorization has run. `info == n + 1` only
+        // warns that `A` is singular to working precision.
+        if info >= 0 && info as usize <= n + 1 {
+            ffi::Destroy_SuperNode_Matrix(&mut l_mat);
+            ffi::Destroy_CompCol_Matrix(&mut u_mat);
+        }
+        ffi::StatFree(&mut stat);
+
+        if info != 0 && info as usize != n + 1 {
+            return Err(SolverError::from_info(info, n));
+        }
+        let solution = if nrhs == 0 {
+            Vec::new()
+        } else {
+            match x_mat.dense_values() {
+                None => return Err(SolverError::Unsolvable),
+                Some(data) => columns_from_dense_data(data, n),
+            }
+        };
+        let mut equed = Equilibration::from_equed(equed);
+        if transposed {
+            equed = equed.transposed();
+            mem::swap(&mut row_scale, &mut col_scale);
+        }
+        // Both scalings are computed, but only the ones in `equed` applied.
+        if !matches!(equed, Equilibration::Row | Equilibration::Both) {
+            row_scale.fill(one);
+        }
+        if !matches!(equed, Equilibration::Column | Equilibration::Both) {
+            col_scale.fill(one);
+        }
+        Ok(SolveReport {
+            solution,
+            rcond,
+            recip_pivot_growth,
+            ferr,
+            berr,
+            equed,
+            row_scale,
+            col_scale,
+        })
+    })
+}
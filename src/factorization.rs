@@ -0,0 +1,600 @@
+use crate::dense::column_major_copy;
+use crate::{bindings, pattern};
+use crate::{
+    columns_from_dense_data, vec_of_array1_to_array2, CompColMatrix, Options,
+    PermutedCompColMatrix, SolverError, SuperLuScalar, SuperMatrix, SupernodeMatrix,
+};
+use libc::c_int;
+use ndarray::{s, Array1, Array2, ArrayViewMut2, ShapeBuilder};
+use num_traits::{Float, Zero};
+use sprs::{CsMat, CsVec, PermOwned};
+use std::marker::PhantomData;
+use std::mem;
+use superlu_sys as ffi;
+
+/// LU factorization `Pr * A * Pc = L * U` computed by `?gstrf`.
+///
+/// The factors and permutations are kept alive until the value is dropped,
+/// so the same matrix can be solved against any number of right-hand sides.
+pub struct LuFactorization<T: SuperLuScalar = f64> {
+    /// `None` until a factorization succeeded.
+    l_mat: Option<SupernodeMatrix>,
+    u_mat: Option<CompColMatrix<'static>>,
+    perm_c: Vec<c_int>,
+    perm_r: Vec<c_int>,
+    etree: Vec<c_int>,
+    glu: bindings::GlobalLU_t,
+    options: ffi::superlu_options_t,
+    colptr: Vec<usize>,
+    rowind: Vec<usize>,
+    scalar: PhantomData<T>,
+}
+
+unsafe impl<T: SuperLuScalar> Send for LuFactorization<T> {}
+
+impl<T: SuperLuScalar> LuFactorization<T> {
+    /// Factors `a`. CSR input is converted to CSC, as `?gstrf` only works on
+    /// columns.
+    pub fn new(a: CsMat<T>, options: &Options) -> Result<Self, SolverError> {
+        let a = if a.is_csc() { a } else { a.to_csc() };
+        let m = a.rows();
+        let n = a.cols();
+        if m != n {
+            return Err(SolverError::DimensionMismatch {
+                expected: m,
+                found: n,
+            });
+        }
+        if a.nnz() == 0 {
+            return Err(SolverError::Singular { column: 0 });
+        }
+        if let Some(column) = pattern::structurally_singular_column(a.view()) {
+            return Err(SolverError::Singular { column });
+        }
+
+        let mut ffi_options = options.ffi;
+        ffi_options.Fact = ffi::fact_t::DOFACT;
+
+        let mut factorization = LuFactorization {
+            l_mat: None,
+            u_mat: None,
+            perm_c: options.initial_perm_c(n)?,
+            perm_r: vec![0; m],
+            etree: vec![0; n],
+            glu: unsafe { mem::zeroed() },
+            options: ffi_options,
+            colptr: a.indptr().to_proper().into_owned(),
+            rowind: a.indices().to_vec(),
+            scalar: PhantomData,
+        };
+
+        let mut a_mat = SuperMatrix::from_csc_mat(a);
+        unsafe {
+            if !matches!(ffi_options.ColPerm, ffi::colperm_t::MY_PERMC) {
+                bindings::get_perm_c(
+                    ffi_options.ColPerm as c_int,
+                    a_mat.raw_mut(),
+                    factorization.perm_c.as_mut_ptr(),
+                );
+            }
+        }
+        factorization.factorize(&mut a_mat)?;
+        Ok(factorization)
+    }
+
+    fn factorize(&mut self, a_mat: &mut SuperMatrix) -> Result<(), SolverError> {
+        let n = self.ncols();
+        let mut info = 0;
+        // With `SamePattern_SameRowPerm` the existing factors are overwritten
+        // in place, otherwise they have been dropped and are allocated anew.
+        let mut l_mat = match self.l_mat.take() {
+            Some(l_mat) => l_mat.into_raw(),
+            None => unsafe { mem::zeroed() },
+        };
+        let mut u_mat = match self.u_mat.take() {
+            Some(u_mat) => u_mat.into_raw(),
+            None => unsafe { mem::zeroed() },
+        };
+        unsafe {
+            let mut ac_mat: ffi::SuperMatrix = mem::zeroed();
+            ffi::sp_preorder(
+                &mut self.options,
+                a_mat.raw_mut(),
+                self.perm_c.as_mut_ptr(),
+                self.etree.as_mut_ptr(),
+                &mut ac_mat,
+            );
+            let mut ac_mat = PermutedCompColMatrix::from_superlu(ac_mat);
+
+            let panel_size = ffi::sp_ienv(1);
+            let relax = ffi::sp_ienv(2);
+
+            let mut stat: ffi::SuperLUStat_t = mem::zeroed();
+            ffi::StatInit(&mut stat);
+
+            T::gstrf(
+                &mut self.options,
+                ac_mat.raw_mut(),
+                relax,
+                panel_size,
+                self.etree.as_mut_ptr(),
+                std::ptr::null_mut(),
+                0,
+                self.perm_c.as_mut_ptr(),
+                self.perm_r.as_mut_ptr(),
+                &mut l_mat,
+                &mut u_mat,
+                &mut self.glu,
+                &mut stat,
+                &mut info,
+            );
+
+            ffi::StatFree(&mut stat);
+
+            // The factorization ran to completion, so L and U are allocated.
+            // They are only kept if they can be solved with.
+            if info >= 0 && info as usize <= n {
+                let l_mat = SupernodeMatrix::from_superlu(l_mat);
+                let u_mat = CompColMatrix::from_superlu(u_mat);
+                if info == 0 {
+                    self.l_mat = Some(l_mat);
+                    self.u_mat = Some(u_mat);
+                }
+            }
+        }
+
+        if info == 0 {
+            Ok(())
+        } else {
+            Err(SolverError::from_info(info, n))
+        }
+    }
+
+    /// Refactors a matrix with the same sparsity pattern but new values.
+    ///
+    /// The column permutation and elimination tree of the previous
+    /// factorization are reused (`Fact = SamePattern`), so only the numeric
+    /// factorization with partial pivoting is repeated.
+    pub fn refactor(&mut self, new_values: &CsMat<T>) -> Result<(), SolverError> {
+        self.refactor_with(new_values, ffi::fact_t::SamePattern)
+    }
+
+    /// Refactors a matrix with the same sparsity pattern but new values,
+    /// reusing the row permutation and the storage of `L` and `U` as well
+    /// (`Fact = SamePattern_SameRowPerm`).
+    ///
+    /// No new pivoting takes place, so this is only numerically safe when the
+    /// new values are close to the previous ones. If the previous factorization
+    /// failed, this falls back to [`LuFactorization::refactor`].
+    pub fn refactor_same_row_perm(&mut self, new_values: &CsMat<T>) -> Result<(), SolverError> {
+        if self.l_mat.is_some() {
+            self.refactor_with(new_values, ffi::fact_t::SamePattern_SameRowPerm)
+        } else {
+            self.refactor_with(new_values, ffi::fact_t::SamePattern)
+        }
+    }
+
+    fn refactor_with(
+        &mut self,
+        new_values: &CsMat<T>,
+        fact: ffi::fact_t,
+    ) -> Result<(), SolverError> {
+        let new_values = new_values.to_csc();
+        if !self.has_pattern_of(&new_values) {
+            return Err(SolverError::Conflict);
+        }
+        if let ffi::fact_t::SamePattern = fact {
+            // L and U are allocated afresh for a new row permutation.
+            self.l_mat = None;
+            self.u_mat = None;
+        }
+        self.options.Fact = fact;
+
+        let mut a_mat = SuperMatrix::from_csc_mat(new_values);
+        self.factorize(&mut a_mat)
+    }
+
+    fn has_pattern_of(&self, mat: &CsMat<T>) -> bool {
+        if mat.rows() != self.nrows()
+            || mat.cols() != self.ncols()
+            || mat.nnz() != self.rowind.len()
+        {
+            return false;
+        }
+        (0..mat.cols()).all(|j| {
+            let range = mat.indptr().outer_inds_sz(j);
+            let own_range = self.colptr[j]..self.colptr[j + 1];
+            mat.indices()[range] == self.rowind[own_range]
+        })
+    }
+
+    /// Solves `A x = b` for every column in `b` with the stored factors.
+    pub fn solve(&self, b: &[Array1<T>]) -> Result<Vec<Array1<T>>, SolverError> {
+        self.solve_with(ffi::trans_t::NOTRANS, b)
+    }
+
+    /// Solves `A^T x = b` for every column in `b` with the stored factors.
+    pub fn solve_transpose(&self, b: &[Array1<T>]) -> Result<Vec<Array1<T>>, SolverError> {
+        self.solve_with(ffi::trans_t::TRANS, b)
+    }
+
+    /// Solves `A^H x = b` for every column in `b` with the stored factors.
+    /// The same as [`LuFactorization::solve_transpose`] for real matrices.
+    pub fn solve_adjoint(&self, b: &[Array1<T>]) -> Result<Vec<Array1<T>>, SolverError> {
+        self.solve_with(ffi::trans_t::CONJ, b)
+    }
+
+    /// Solves `A X = B` for the columns of `b`, overwriting it with the
+    /// solution.
+    ///
+    /// A column-major `b`, such as a Fortran-ordered array, a slice of its
+    /// rows or a contiguous vector with an axis inserted, is solved in place
+    /// without any allocation on the Rust side. Other layouts are solved in a
+    /// column-major copy.
+    pub fn solve_into(&self, mut b: ArrayViewMut2<T>) -> Result<(), SolverError> {
+        let (Some(l_mat), Some(u_mat)) = (&self.l_mat, &self.u_mat) else {
+            return Err(SolverError::Unsolvable);
+        };
+        if b.nrows() != self.nrows() {
+            return Err(SolverError::DimensionMismatch {
+                expected: self.nrows(),
+                found: b.nrows(),
+            });
+        }
+        let trans = ffi::trans_t::NOTRANS;
+        if let Some(mut b_mat) = SuperMatrix::from_ndarray_view_mut(b.view_mut()) {
+            return gstrs::<T>(l_mat, u_mat, &self.perm_c, &self.perm_r, trans, &mut b_mat);
+        }
+        let mut x = column_major_copy(b.view());
+        let mut b_mat =
+            SuperMatrix::from_ndarray_view_mut(x.view_mut()).expect("column-major copy");
+        gstrs::<T>(l_mat, u_mat, &self.perm_c, &self.perm_r, trans, &mut b_mat)?;
+        drop(b_mat);
+        b.assign(&x);
+        Ok(())
+    }
+
+    /// Solves `A x = b` for a sparse `b`, dropping the entries of `x` whose
+    /// modulus is at most `drop_tolerance`.
+    pub fn solve_sparse(
+        &self,
+        b: &CsVec<T>,
+        drop_tolerance: T::Real,
+    ) -> Result<CsVec<T>, SolverError> {
+        let block = CsMat::new_csc(
+            (b.dim(), 1),
+            vec![0, b.nnz()],
+            b.indices().to_vec(),
+            b.data().to_vec(),
+        );
+        let (_, indices, data) = self
+            .solve_sparse_block(&block, drop_tolerance)?
+            .into_raw_storage();
+        Ok(CsVec::new(self.ncols(), indices, data))
+    }
+
+    /// Solves `A X = B` for a sparse block of right-hand sides, returning `X`
+    /// in CSC format without the entries whose modulus is at most
+    /// `drop_tolerance`. NaN entries are kept, so a bad `B` stays visible.
+    ///
+    /// The columns are solved a few at a time in a dense work array, so only
+    /// the kept entries are stored per right-hand side.
+    pub fn solve_sparse_block(
+        &self,
+        b: &CsMat<T>,
+        drop_tolerance: T::Real,
+    ) -> Result<CsMat<T>, SolverError> {
+        // Enough columns for ?gstrs to work on a block at a time.
+        const BLOCK_SIZE: usize = 32;
+
+        let n = self.nrows();
+        if b.rows() != n {
+            return Err(SolverError::DimensionMismatch {
+                expected: n,
+                found: b.rows(),
+            });
+        }
+        let converted;
+        let b = if b.is_csc() {
+            b.view()
+        } else {
+            converted = b.to_csc();
+            converted.view()
+        };
+
+        let mut work = Array2::zeros((n, BLOCK_SIZE.min(b.cols())).f());
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for start in (0..b.cols()).step_by(BLOCK_SIZE) {
+            let end = (start + BLOCK_SIZE).min(b.cols());
+            let mut block = work.slice_mut(s![.., ..end - start]);
+            for (mut column, rhs) in block.columns_mut().into_iter().zip(start..end) {
+                if let Some(rhs) = b.outer_view(rhs) {
+                    for (row, &value) in rhs.iter() {
+                        column[row] = value;
+                    }
+                }
+            }
+
+            self.solve_into(block.view_mut())?;
+            // Gathering the kept entries also clears the block for reuse.
+            for mut column in block.columns_mut() {
+                for (row, value) in column.iter_mut().enumerate() {
+                    let modulus = value.modulus();
+                    if modulus > drop_tolerance || modulus.is_nan() {
+                        indices.push(row);
+                        data.push(*value);
+                    }
+                    *value = T::zero();
+                }
+                indptr.push(indices.len());
+            }
+        }
+        Ok(CsMat::new_csc(
+            (self.ncols(), b.cols()),
+            indptr,
+            indices,
+            data,
+        ))
+    }
+
+    fn solve_with(
+        &self,
+        trans: ffi::trans_t,
+        b: &[Array1<T>],
+    ) -> Result<Vec<Array1<T>>, SolverError> {
+        let (Some(l_mat), Some(u_mat)) = (&self.l_mat, &self.u_mat) else {
+            return Err(SolverError::Unsolvable);
+        };
+        solve_with_factors(l_mat, u_mat, &self.perm_c, &self.perm_r, trans, b)
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.perm_r.len()
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.perm_c.len()
+    }
+
+    /// Row permutation: row `i` of `A` is row `perm_r[i]` of `Pr * A`.
+    pub fn perm_r(&self) -> &[c_int] {
+        &self.perm_r
+    }
+
+    /// Column permutation: column `j` of `A` is column `perm_c[j]` of `A * Pc`.
+    pub fn perm_c(&self) -> &[c_int] {
+        &self.perm_c
+    }
+
+    /// The unit lower triangular factor `L`, `None` if the last
+    /// factorization failed.
+    pub fn l_factor(&self) -> Option<&SupernodeMatrix> {
+        self.l_mat.as_ref()
+    }
+
+    /// The upper triangular factor `U`, `None` if the last factorization
+    /// failed. Its supernodal part is stored in `L`, see
+    /// [`LuFactorization::l_factor`].
+    pub fn u_factor(&self) -> Option<&CompColMatrix<'static>> {
+        self.u_mat.as_ref()
+    }
+
+    /// `L` as a CSC matrix with unit diagonal, in the row order of `Pr * A`.
+    /// `None` if the last factorization failed.
+    pub fn l_matrix(&self) -> Option<CsMat<T>> {
+        let l_mat = self.l_mat.as_ref()?;
+        let n = self.ncols();
+        let columns = (0..n)
+            .map(|j| {
+                let (rows, values) = l_mat.column::<T>(j)?;
+                let below = rows.iter().zip(values).filter(|(&row, _)| row as usize > j);
+                let column = std::iter::once((j, T::one()))
+                    .chain(below.map(|(&row, &value)| (row as usize, value)))
+                    .collect();
+                Some(column)
+            })
+            .collect::<Option<_>>()?;
+        Some(csc_from_columns(n, columns))
+    }
+
+    /// `U` as a CSC matrix, in the row and column order of `Pr * A * Pc`.
+    /// `None` if the last factorization failed.
+    pub fn u_matrix(&self) -> Option<CsMat<T>> {
+        let (l_mat, u_mat) = (self.l_mat.as_ref()?, self.u_mat.as_ref()?);
+        let u_values = u_mat.nzval::<T>()?;
+        let (u_colptr, u_rowind) = (u_mat.colptr(), u_mat.rowind());
+        let n = self.ncols();
+        let columns = (0..n)
+            .map(|j| {
+                // The rows of `U` within the supernode of `j` are stored in `L`.
+                let (rows, values) = l_mat.column::<T>(j)?;
+                let supernodal = rows
+                    .iter()
+                    .zip(values)
+                    .filter(|(&row, _)| row as usize <= j);
+                let range = u_colptr[j] as usize..u_colptr[j + 1] as usize;
+                let column = supernodal
+                    .chain(u_rowind[range.clone()].iter().zip(&u_values[range]))
+                    .map(|(&row, &value)| (row as usize, value))
+                    .collect();
+                Some(column)
+            })
+            .collect::<Option<_>>()?;
+        Some(csc_from_columns(n, columns))
+    }
+
+    /// Determinant of `A`.
+    ///
+    /// This overflows or underflows for large matrices, where
+    /// [`LuFactorization::log_abs_determinant`] and
+    /// [`LuFactorization::sign`] should be used instead.
+    pub fn determinant(&self) -> Result<T, SolverError> {
+        let (sign, log_abs) = self.sign_and_log_abs_determinant()?;
+        Ok(sign * T::from_real(log_abs.exp()))
+    }
+
+    /// Natural logarithm of `|det A|`.
+    pub fn log_abs_determinant(&self) -> Result<T::Real, SolverError> {
+        Ok(self.sign_and_log_abs_determinant()?.1)
+    }
+
+    /// `det A / |det A|`, which is `1` or `-1` for real matrices and a
+    /// complex number of modulus one for complex matrices.
+    pub fn sign(&self) -> Result<T, SolverError> {
+        Ok(self.sign_and_log_abs_determinant()?.0)
+    }
+
+    /// `det A` is the product of the diagonal of `U`, times the signs of both
+    /// permutations. Summing logarithms instead of multiplying avoids
+    /// overflow. The factors are only kept for a nonsingular `A`, so every
+    /// pivot is nonzero.
+    fn sign_and_log_abs_determinant(&self) -> Result<(T, T::Real), SolverError> {
+        let l_mat = self.l_mat.as_ref().ok_or(SolverError::Unsolvable)?;
+        let mut sign = if is_odd(&self.perm_r) != is_odd(&self.perm_c) {
+            T::zero() - T::one()
+        } else {
+            T::one()
+        };
+        let mut log_abs = T::Real::zero();
+        for j in 0..self.ncols() {
+            // The diagonal of `U` is stored in the supernodes of `L`.
+            let (rows, values) = l_mat.column::<T>(j).ok_or(SolverError::Unsolvable)?;
+            let pivot = rows
+                .iter()
+                .zip(values)
+                .find(|(&row, _)| row as usize == j)
+                .map(|(_, &value)| value)
+                .ok_or(SolverError::Unsolvable)?;
+            let modulus = pivot.modulus();
+            sign = sign * (pivot / T::from_real(modulus));
+            log_abs = log_abs + modulus.ln();
+        }
+        Ok((sign, log_abs))
+    }
+
+    /// Row permutation `Pr` in the convention of `sprs`, where `Pr * x`
+    /// takes entry `i` from entry `pr.at(i)` of `x`.
+    pub fn row_permutation(&self) -> PermOwned {
+        inverse_permutation(&self.perm_r)
+    }
+
+    /// Column permutation `Pc` in the convention of `sprs`, where `A * Pc`
+    /// takes column `j` from column `pc.at(j)` of `A`.
+    pub fn col_permutation(&self) -> PermOwned {
+        inverse_permutation(&self.perm_c)
+    }
+}
+
+/// Builds an `n x n` CSC matrix from unsorted `(row, value)` entries per
+/// column.
+fn csc_from_columns<T: SuperLuScalar>(n: usize, columns: Vec<Vec<(usize, T)>>) -> CsMat<T> {
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(0);
+    for mut column in columns {
+        column.sort_unstable_by_key(|&(row, _)| row);
+        for (row, value) in column {
+            indices.push(row);
+            data.push(value);
+        }
+        indptr.push(indices.len());
+    }
+    CsMat::new_csc((n, n), indptr, indices, data)
+}
+
+/// SuperLU stores where each index goes, `sprs` where it comes from.
+fn inverse_permutation(perm: &[c_int]) -> PermOwned {
+    let mut inverse = vec![0; perm.len()];
+    for (i, &p) in perm.iter().enumerate() {
+        inverse[p as usize] = i;
+    }
+    PermOwned::new(inverse)
+}
+
+/// Whether `perm` is an odd permutation, i.e. has an odd number of cycles of
+/// even length.
+fn is_odd(perm: &[c_int]) -> bool {
+    let mut visited = vec![false; perm.len()];
+    let mut odd = false;
+    for start in 0..perm.len() {
+        let mut i = start;
+        let mut length = 0;
+        while !visited[i] {
+            visited[i] = true;
+            i = perm[i] as usize;
+            length += 1;
+        }
+        if length > 0 && length % 2 == 0 {
+            odd = !odd;
+        }
+    }
+    odd
+}
+
+/// Solves with the factors of a complete or incomplete factorization.
+pub(crate) fn solve_with_factors<T: SuperLuScalar>(
+    l_mat: &SupernodeMatrix,
+    u_mat: &CompColMatrix,
+    perm_c: &[c_int],
+    perm_r: &[c_int],
+    trans: ffi::trans_t,
+    b: &[Array1<T>],
+) -> Result<Vec<Array1<T>>, SolverError> {
+    let n = perm_c.len();
+    if b.is_empty() {
+        return Ok(Vec::new());
+    }
+    for rhs_col in b {
+        if rhs_col.len() != n {
+            return Err(SolverError::DimensionMismatch {
+                expected: n,
+                found: rhs_col.len(),
+            });
+        }
+    }
+
+    let mut b_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    gstrs::<T>(l_mat, u_mat, perm_c, perm_r, trans, &mut b_mat)?;
+
+    match b_mat.dense_values() {
+        None => Err(SolverError::Unsolvable),
+        Some(data) => Ok(columns_from_dense_data(data, n)),
+    }
+}
+
+/// Runs `?gstrs`, overwriting `b_mat` with the solution.
+fn gstrs<T: SuperLuScalar>(
+    l_mat: &SupernodeMatrix,
+    u_mat: &CompColMatrix,
+    perm_c: &[c_int],
+    perm_r: &[c_int],
+    trans: ffi::trans_t,
+    b_mat: &mut SuperMatrix,
+) -> Result<(), SolverError> {
+    let mut info = 0;
+    unsafe {
+        let mut stat: ffi::SuperLUStat_t = mem::zeroed();
+        ffi::StatInit(&mut stat);
+
+        // ?gstrs only reads the factors and permutations.
+        T::gstrs(
+            trans,
+            l_mat.raw() as *const _ as *mut _,
+            u_mat.raw() as *const _ as *mut _,
+            perm_c.as_ptr() as *mut _,
+            perm_r.as_ptr() as *mut _,
+            b_mat.raw_mut(),
+            &mut stat,
+            &mut info,
+        );
+
+        ffi::StatFree(&mut stat);
+    }
+    if info != 0 {
+        return Err(SolverError::from_info(info, perm_c.len()));
+    }
+    Ok(())
+}
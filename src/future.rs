@@ -0,0 +1,112 @@
+use crate::{pool, prepare_gssv, Options, SolverError, SuperLuScalar};
+use ndarray::Array1;
+use sprs::CsMat;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+type Solution<T> = Result<Vec<Array1<T>>, SolverError>;
+
+/// Solves `A x = b` on the blocking pool and returns a future for the
+/// solution.
+///
+/// The future does not depend on a particular runtime, any executor can poll
+/// it. `a` and `b` are moved to the pool, where the system is checked and
+/// copied for SuperLU, so the calling thread does no work proportional to
+/// their size. Dropping the future cancels the solve if it has not started
+/// yet. A running factorization cannot be interrupted, its result is
+/// discarded. To bound the waiting time, wrap the future in the runtime's
+/// timeout.
+pub fn solve_super_lu_async<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: Vec<Array1<T>>,
+    options: &mut Options,
+) -> SolveFuture<T> {
+    let options = options.clone();
+    spawn(move || prepare_gssv(a, &b, &options).and_then(|task| task()))
+}
+
+/// Runs `task` on the blocking pool unless the returned future is dropped
+/// before a worker picks it up.
+pub(crate) fn spawn<T: SuperLuScalar>(
+    task: impl FnOnce() -> Solution<T> + Send + 'static,
+) -> SolveFuture<T> {
+    let slot = Arc::new(Mutex::new(Slot {
+        solution: None,
+        waker: None,
+        cancelled: false,
+    }));
+    let worker_slot = Arc::clone(&slot);
+    pool::spawn(move || {
+        if worker_slot.lock().unwrap().cancelled {
+            return;
+        }
+        let solution =
+            panic::catch_unwind(AssertUnwindSafe(task)).unwrap_or(Err(SolverError::Unsolvable));
+        let mut slot = worker_slot.lock().unwrap();
+        if slot.cancelled {
+            return;
+        }
+        slot.solution = Some(solution);
+        let waker = slot.waker.take();
+        drop(slot);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    SolveFuture {
+        state: State::Pending(slot),
+    }
+}
+
+/// Future returned by [`solve_super_lu_async`].
+pub struct SolveFuture<T: SuperLuScalar> {
+    state: State<T>,
+}
+
+enum State<T> {
+    Pending(Arc<Mutex<Slot<T>>>),
+    Done(Option<Solution<T>>),
+}
+
+/// Shared between the future and the job on the pool.
+struct Slot<T> {
+    solution: Option<Solution<T>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+}
+
+impl<T: SuperLuScalar> Future for SolveFuture<T> {
+    type Output = Solution<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let solution = match &mut self.state {
+            State::Done(solution) => solution.take(),
+            State::Pending(slot) => {
+                let mut slot = slot.lock().unwrap();
+                match slot.solution.take() {
+                    Some(solution) => Some(solution),
+                    None => {
+                        if !slot.waker.as_ref().is_some_and(|w| w.will_wake(cx.waker())) {
+                            slot.waker = Some(cx.waker().clone());
+                        }
+                        return Poll::Pending;
+                    }
+                }
+            }
+        };
+        let solution = solution.expect("SolveFuture polled after completion");
+        self.state = State::Done(None);
+        Poll::Ready(solution)
+    }
+}
+
+impl<T: SuperLuScalar> Drop for SolveFuture<T> {
+    fn drop(&mut self) {
+        if let State::Pending(slot) = &self.state {
+            slot.lock().unwrap().cancelled = true;
+        }
+    }
+}
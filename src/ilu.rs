@@ -0,0 +1,168 @@
+use crate::factorization::solve_with_factors;
+use crate::{bindings, pattern};
+use crate::{
+    CompColMatrix, Options, PermutedCompColMatrix, SolverError, SuperLuScalar, SuperMatrix,
+    SupernodeMatrix,
+};
+use libc::c_int;
+use ndarray::Array1;
+use sprs::CsMat;
+use std::marker::PhantomData;
+use std::mem;
+use superlu_sys as ffi;
+
+/// Threshold incomplete LU factorization `Pr * A * Pc ≈ L * U` computed by
+/// `?gsitrf`, meant as a preconditioner.
+///
+/// Entries are dropped according to the options' ILU settings, see
+/// [`crate::OptionsBuilder::drop_tolerance`], and `ILU_DropTol = 0` gives a
+/// complete factorization. As for [`crate::LuFactorization`], `A` is factored
+/// as it is: `Equil` and `RowPerm` are ignored.
+pub struct IncompleteLu<T: SuperLuScalar = f64> {
+    l_mat: SupernodeMatrix,
+    u_mat: CompColMatrix<'static>,
+    perm_c: Vec<c_int>,
+    perm_r: Vec<c_int>,
+    zero_pivots: usize,
+    scalar: PhantomData<T>,
+}
+
+unsafe impl<T: SuperLuScalar> Send for IncompleteLu<T> {}
+
+impl<T: SuperLuScalar> IncompleteLu<T> {
+    /// Factors `a`. CSR input is converted to CSC.
+    pub fn new(a: CsMat<T>, options: &Options) -> Result<Self, SolverError> {
+        let a = if a.is_csc() { a } else { a.to_csc() };
+        let n = a.cols();
+        if a.rows() != n {
+            return Err(SolverError::DimensionMismatch {
+                expected: a.rows(),
+                found: n,
+            });
+        }
+        if a.nnz() == 0 {
+            return Err(SolverError::Singular { column: 0 });
+        }
+        if let Some(column) = pattern::structurally_singular_column(a.view()) {
+            return Err(SolverError::Singular { column });
+        }
+
+        let mut ffi_options = options.ffi;
+        ffi_options.Fact = ffi::fact_t::DOFACT;
+        let mut perm_c = options.initial_perm_c(n)?;
+        let mut perm_r: Vec<c_int> = vec![0; n];
+        let mut etree: Vec<c_int> = vec![0; n];
+
+        let mut a_mat = SuperMatrix::from_csc_mat(a);
+        let mut info = 0;
+        unsafe {
+            if !matches!(ffi_options.ColPerm, ffi::colperm_t::MY_PERMC) {
+                bindings::get_perm_c(
+                    ffi_options.ColPerm as c_int,
+                    a_mat.raw_mut(),
+                    perm_c.as_mut_ptr(),
+                );
+            }
+
+            let mut ac_mat: ffi::SuperMatrix = mem::zeroed();
+            ffi::sp_preorder(
+                &mut ffi_options,
+                a_mat.raw_mut(),
+                perm_c.as_mut_ptr(),
+                etree.as_mut_ptr(),
+                &mut ac_mat,
+            );
+            let mut ac_mat = PermutedCompColMatrix::from_superlu(ac_mat);
+
+            let panel_size = ffi::sp_ienv(1);
+            let relax = ffi::sp_ienv(2);
+
+            let mut l_mat: ffi::SuperMatrix = mem::zeroed();
+            let mut u_mat: ffi::SuperMatrix = mem::zeroed();
+            let mut glu: bindings::GlobalLU_t = mem::zeroed();
+            let mut stat: ffi::SuperLUStat_t = mem::zeroed();
+            ffi::StatInit(&mut stat);
+
+            T::gsitrf(
+                &mut ffi_options,
+                ac_mat.raw_mut(),
+                relax,
+                panel_size,
+                etree.as_mut_ptr(),
+                std::ptr::null_mut(),
+                0,
+                perm_c.as_mut_ptr(),
+                perm_r.as_mut_ptr(),
+                &mut l_mat,
+                &mut u_mat,
+                &mut glu,
+                &mut stat,
+                &mut info,
+            );
+
+            ffi::StatFree(&mut stat);
+
+            // Up to `n`, `info` counts zero pivots that were replaced by small
+            // values, so the factors are still usable.
+            if info >= 0 && info as usize <= n {
+                return Ok(IncompleteLu {
+                    l_mat: SupernodeMatrix::from_superlu(l_mat),
+                    u_mat: CompColMatrix::from_superlu(u_mat),
+                    perm_c,
+                    perm_r,
+                    zero_pivots: info as usize,
+                    scalar: PhantomData,
+                });
+            }
+        }
+        Err(SolverError::from_info(info, n))
+    }
+
+    /// Applies the preconditioner, returning `z = (L * U)^-1 * r` up to the
+    /// permutations.
+    pub fn apply(&self, r: &Array1<T>) -> Result<Array1<T>, SolverError> {
+        let mut z = self.solve(std::slice::from_ref(r))?;
+        Ok(z.remove(0))
+    }
+
+    /// Applies the preconditioner to every column in `r`.
+    pub fn solve(&self, r: &[Array1<T>]) -> Result<Vec<Array1<T>>, SolverError> {
+        solve_with_factors(
+            &self.l_mat,
+            &self.u_mat,
+            &self.perm_c,
+            &self.perm_r,
+            ffi::trans_t::NOTRANS,
+            r,
+        )
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.perm_r.len()
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.perm_c.len()
+    }
+
+    /// Number of zero pivots that were replaced by small values, according to
+    /// `ILU_FillTol`.
+    pub fn zero_pivots(&self) -> usize {
+        self.zero_pivots
+    }
+
+    /// Number of stored entries in `L` and `U`, a measure of the fill.
+    pub fn nnz(&self) -> usize {
+        self.l_mat.nnz() + self.u_mat.nnz()
+    }
+
+    /// Row permutation: row `i` of `A` is row `perm_r[i]` of `Pr * A`.
+    pub fn perm_r(&self) -> &[c_int] {
+        &self.perm_r
+    }
+
+    /// Column permutation: column `j` of `A` is column `perm_c[j]` of `A * Pc`.
+    pub fn perm_c(&self) -> &[c_int] {
+        &self.perm_c
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{IncompleteLu, LuFactorization, SolverError};
+use ndarray::Array1;
+use sprs::CsMat;
+
+/// Approximate inverse `M^-1` of `A`, applied once per Krylov iteration.
+pub trait Preconditioner {
+    /// Returns `z = M^-1 r`.
+    fn apply(&self, r: &Array1<f64>) -> Result<Array1<f64>, SolverError>;
+}
+
+impl Preconditioner for IncompleteLu<f64> {
+    fn apply(&self, r: &Array1<f64>) -> Result<Array1<f64>, SolverError> {
+        IncompleteLu::apply(self, r)
+    }
+}
+
+/// Turns the Krylov method into a refinement of the direct solve, which
+/// converges in one or two iterations.
+impl Preconditioner for LuFactorization<f64> {
+    fn apply(&self, r: &Array1<f64>) -> Result<Array1<f64>, SolverError> {
+        let mut z = self.solve(std::slice::from_ref(r))?;
+        Ok(z.remove(0))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KrylovOptions {
+    /// Stops once `||b - A x|| <= tolerance * ||b||`.
+    pub tolerance: f64,
+    /// Maximum number of matrix-vector products with `A` for GMRES, and of
+    /// iterations (two products each) for BiCGSTAB.
+    pub max_iterations: usize,
+    /// Dimension of the Krylov space before GMRES restarts.
+    pub restart: usize,
+}
+
+impl Default for KrylovOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-8,
+            max_iterations: 1000,
+            restart: 30,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvergenceReason {
+    /// The relative residual dropped below the tolerance.
+    Converged,
+    MaxIterations,
+    /// The method cannot continue, e.g. a BiCGSTAB inner product vanished.
+    Breakdown,
+}
+
+#[derive(Clone, Debug)]
+pub struct KrylovReport {
+    /// The last iterate, also if the method did not converge.
+    pub solution: Array1<f64>,
+    pub iterations: usize,
+    /// Relative residual `||b - A x|| / ||b||`, starting with the initial
+    /// guess `x = 0`. GMRES reports the estimate from its least squares
+    /// problem, which equals the true residual in exact arithmetic.
+    pub residual_history: Vec<f64>,
+    pub reason: ConvergenceReason,
+}
+
+/// Solves `A x = b` with restarted GMRES(m), right-preconditioned by `M`.
+pub fn gmres(
+    a: &CsMat<f64>,
+    b: &Array1<f64>,
+    preconditioner: Option<&dyn Preconditioner>,
+    options: &KrylovOptions,
+) -> Result<KrylovReport, SolverError> {
+    let n = check_dimensions(a, b)?;
+    let precondition = |v: &Array1<f64>| match preconditioner {
+        Some(m) => m.apply(v),
+        None => Ok(v.clone()),
+    };
+    let b_norm = norm(b);
+    let restart = options.restart.max(1);
+
+    let mut x = Array1::zeros(n);
+    let mut history = vec![1.0];
+    let mut iterations = 0;
+    if b_norm == 0.0 {
+        return Ok(report(x, iterations, history, ConvergenceReason::Converged));
+    }
+
+    loop {
+        let r = b - &(a * &x);
+        let beta = norm(&r);
+        if beta <= options.tolerance * b_norm {
+            return Ok(report(x, iterations, history, ConvergenceReason::Converged));
+        }
+        if iterations >= options.max_iterations {
+            return Ok(report(
+                x,
+                iterations,
+                history,
+                ConvergenceReason::MaxIterations,
+            ));
+        }
+
+        // Arnoldi process with modified Gram-Schmidt. The Hessenberg matrix is
+        // kept upper triangular by Givens rotations as it grows, so `g`
+        // holds the residual of the least squares problem at all times.
+        let mut basis = vec![r / beta];
+        let mut preconditioned = Vec::with_capacity(restart);
+        let mut h = vec![vec![0.0; restart]; restart + 1];
+        let mut rotations: Vec<(f64, f64)> = Vec::with_capacity(restart);
+        let mut g = vec![0.0; restart + 1];
+        g[0] = beta;
+
+        let mut k = 0;
+        let mut breakdown = false;
+        while k < restart && iterations < options.max_iterations {
+            let z = precondition(&basis[k])?;
+            let mut w = a * &z;
+            preconditioned.push(z);
+            for (i, v) in basis.iter().enumerate() {
+                h[i][k] = w.dot(v);
+                w.scaled_add(-h[i][k], v);
+            }
+            h[k + 1][k] = norm(&w);
+
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (upper, lower) = (h[i][k], h[i + 1][k]);
+                h[i][k] = c * upper + s * lower;
+                h[i + 1][k] = -s * upper + c * lower;
+            }
+            let (c, s) = givens(h[k][k], h[k + 1][k]);
+            h[k][k] = c * h[k][k] + s * h[k + 1][k];
+            h[k + 1][k] = 0.0;
+            g[k + 1] = -s * g[k];
+            g[k] *= c;
+            rotations.push((c, s));
+
+            let subdiagonal = norm(&w);
+            iterations += 1;
+            k += 1;
+            let residual = g[k].abs();
+            history.push(residual / b_norm);
+            if !residual.is_finite() || h[k - 1][k - 1] == 0.0 {
+                breakdown = true;
+                break;
+            }
+            if residual <= options.tolerance * b_norm {
+                break;
+            }
+            if subdiagonal == 0.0 {
+                // The Krylov space is invariant, so the solution lies in it.
+                break;
+            }
+            basis.push(w / subdiagonal);
+        }
+
+        if breakdown {
+            return Ok(report(x, iterations, history, ConvergenceReason::Breakdown));
+        }
+        // Back substitution for the coefficients `y`, then `x += M^-1 V y`.
+        let mut y = g[..k].to_vec();
+        for i in (0..k).rev() {
+            for j in i + 1..k {
+                y[i] -= h[i][j] * y[j];
+            }
+            y[i] /= h[i][i];
+        }
+        for (coefficient, z) in y.iter().zip(&preconditioned) {
+            x.scaled_add(*coefficient, z);
+        }
+        if g[k].abs() <= options.tolerance * b_norm {
+            return Ok(report(x, iterations, history, ConvergenceReason::Converged));
+        }
+        if k < restart && iterations < options.max_iterations {
+            // An invariant subspace without convergence: restarting would
+            // only repeat the same steps.
+            return Ok(report(x, iterations, history, ConvergenceReason::Breakdown));
+        }
+    }
+}
+
+/// Solves `A x = b` with BiCGSTAB, right-preconditioned by `M`.
+pub fn bicgstab(
+    a: &CsMat<f64>,
+    b: &Array1<f64>,
+    preconditioner: Option<&dyn Preconditioner>,
+    options: &KrylovOptions,
+) -> Result<KrylovReport, SolverError> {
+    let n = check_dimensions(a, b)?;
+    let precondition = |v: &Array1<f64>| match preconditioner {
+        Some(m) => m.apply(v),
+        None => Ok(v.clone()),
+    };
+    let b_norm = norm(b);
+
+    let mut x = Array1::zeros(n);
+    let mut history = vec![1.0];
+    if b_norm == 0.0 {
+        return Ok(report(x, 0, history, ConvergenceReason::Converged));
+    }
+
+    let mut r = b.clone();
+    let r_hat = b.clone();
+    let mut p = Array1::zeros(n);
+    let mut v = Array1::zeros(n);
+    let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+
+    for iteration in 1..=options.max_iterations {
+        let rho_next = r_hat.dot(&r);
+        if rho_next == 0.0 || omega == 0.0 {
+            return Ok(report(
+                x,
+                iteration - 1,
+                history,
+                ConvergenceReason::Breakdown,
+            ));
+        }
+        let beta = (rho_next / rho) * (alpha / omega);
+        p = &r + &((&p - &(&v * omega)) * beta);
+        rho = rho_next;
+
+        let p_hat = precondition(&p)?;
+        v = a * &p_hat;
+        let r_hat_v = r_hat.dot(&v);
+        if r_hat_v == 0.0 {
+            return Ok(report(
+                x,
+                iteration - 1,
+                history,
+                ConvergenceReason::Breakdown,
+            ));
+        }
+        alpha = rho / r_hat_v;
+        x.scaled_add(alpha, &p_hat);
+        let s = &r - &(&v * alpha);
+        if norm(&s) <= options.tolerance * b_norm {
+            history.push(norm(&s) / b_norm);
+            return Ok(report(x, iteration, history, ConvergenceReason::Converged));
+        }
+
+        let s_hat = precondition(&s)?;
+        let t = a * &s_hat;
+        let t_t = t.dot(&t);
+        if t_t == 0.0 {
+            return Ok(report(x, iteration, history, ConvergenceReason::Breakdown));
+        }
+        omega = t.dot(&s) / t_t;
+        x.scaled_add(omega, &s_hat);
+        r = &s - &(&t * omega);
+
+        let residual = norm(&r);
+        history.push(residual / b_norm);
+        if !residual.is_finite() {
+            return Ok(report(x, iteration, history, ConvergenceReason::Breakdown));
+        }
+        if residual <= options.tolerance * b_norm {
+            return Ok(report(x, iteration, history, ConvergenceReason::Converged));
+        }
+    }
+    let iterations = options.max_iterations;
+    Ok(report(
+        x,
+        iterations,
+        history,
+        ConvergenceReason::MaxIterations,
+    ))
+}
+
+fn check_dimensions(a: &CsMat<f64>, b: &Array1<f64>) -> Result<usize, SolverError> {
+    if a.rows() != a.cols() {
+        return Err(SolverError::DimensionMismatch {
+            expected: a.rows(),
+            found: a.cols(),
+        });
+    }
+    if b.len() != a.rows() {
+        return Err(SolverError::DimensionMismatch {
+            expected: a.rows(),
+            found: b.len(),
+        });
+    }
+    Ok(a.rows())
+}
+
+fn report(
+    solution: Array1<f64>,
+    iterations: usize,
+    residual_history: Vec<f64>,
+    reason: ConvergenceReason,
+) -> KrylovReport {
+    KrylovReport {
+        solution,
+        iterations,
+        residual_history,
+        reason,
+    }
+}
+
+fn norm(v: &Array1<f64>) -> f64 {
+    v.dot(v).sqrt()
+}
+
+/// Rotation `(c, s)` that zeroes `b` in `[c s; -s c] * [a; b]`.
+fn givens(a: f64, b: f64) -> (f64, f64) {
+    if b == 0.0 {
+        (1.0, 0.0)
+    } else {
+        let r = a.hypot(b);
+        (a / r, b / r)
+    }
+}
@@ -1,95 +1,315 @@
-use libc::{c_double, c_int};
+use libc::c_int;
 use ndarray::{Array1, Array2};
-use sprs::CsMat;
+use sprs::{CsMat, CsMatViewI, SpIndex};
+use std::fmt;
 use std::mem;
-use std::sync::{mpsc, Arc, Mutex};
+use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 use superlu_sys as ffi;
 
 use std::slice::from_raw_parts_mut;
-use superlu_sys::{Dtype_t, Mtype_t, Stype_t};
 
+mod batch;
+mod bindings;
+mod dense;
+mod expert;
+mod factorization;
+mod future;
+mod ilu;
+mod krylov;
+mod matrix;
+mod options;
+mod pattern;
+mod pool;
+#[cfg(unix)]
+mod process;
+mod scalar;
 mod tests;
 
-#[derive(Debug)]
+pub use batch::solve_batch;
+pub use dense::{solve_super_lu_array, solve_super_lu_in_place, solve_super_lu_vector};
+pub use expert::{solve_expert, Equilibration, SolveReport};
+pub use factorization::LuFactorization;
+pub use future::{solve_super_lu_async, SolveFuture};
+pub use ilu::IncompleteLu;
+pub use krylov::{bicgstab, gmres, ConvergenceReason, KrylovOptions, KrylovReport, Preconditioner};
+pub use matrix::{
+    CompColMatrix, CompRowMatrix, DenseMatrix, FromSuperMatrix, PermutedCompColMatrix, SuperMatrix,
+    SupernodeMatrix,
+};
+pub use options::{
+    ColumnPermutation, DropRule, IterativeRefinement, Milu, Options, OptionsBuilder, OptionsError,
+    Transpose,
+};
+#[cfg(unix)]
+pub use process::solve_super_lu_in_process;
+pub use scalar::SuperLuScalar;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SolverError {
+    /// The input does not fit an existing factorization, e.g. a different
+    /// sparsity pattern.
     Conflict,
+    /// No solution can be read back, e.g. after a failed factorization.
     Unsolvable,
     Timeout,
+    /// `U(column, column)` is exactly zero, counting columns from zero. For a
+    /// structurally singular matrix this is a column of `A` (a row for CSR
+    /// input) that cannot be assigned a pivot.
+    Singular {
+        column: usize,
+    },
+    /// SuperLU failed to allocate memory after `bytes` bytes were allocated.
+    OutOfMemory {
+        bytes: usize,
+    },
+    /// SuperLU rejected its argument at the given one-based position.
+    InvalidArgument {
+        position: usize,
+    },
+    DimensionMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The solver process ended without a result, e.g. because SuperLU
+    /// aborted on a failed allocation.
+    Aborted,
+    /// `Options::perm_c` does not contain every column index exactly once.
+    InvalidPermutation,
 }
 
-pub struct Options {
-    pub ffi: ffi::superlu_options_t,
+impl SolverError {
+    /// Decodes a nonzero `info` value returned by a SuperLU driver for an
+    /// `n` column matrix.
+    pub(crate) fn from_info(info: c_int, n: usize) -> Self {
+        if info < 0 {
+            SolverError::InvalidArgument {
+                position: (-info) as usize,
+            }
+        } else if info as usize <= n {
+            SolverError::Singular {
+                column: info as usize - 1,
+            }
+        } else {
+            SolverError::OutOfMemory {
+                bytes: info as usize - n,
+            }
+        }
+    }
 }
 
-impl Default for Options {
-    fn default() -> Self {
-        let mut options: ffi::superlu_options_t = unsafe { mem::zeroed() };
-        unsafe {
-            ffi::set_default_options(&mut options);
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Conflict => write!(f, "input conflicts with the factorization"),
+            SolverError::Unsolvable => write!(f, "system could not be solved"),
+            SolverError::Timeout => write!(f, "solver timed out"),
+            SolverError::Singular { column } => {
+                write!(f, "matrix is singular, zero pivot in column {}", column)
+            }
+            SolverError::OutOfMemory { bytes } => {
+                write!(f, "out of memory after allocating {} bytes", bytes)
+            }
+            SolverError::InvalidArgument { position } => {
+                write!(f, "invalid argument at position {}", position)
+            }
+            SolverError::DimensionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "dimension mismatch, expected {} but found {}",
+                    expected, found
+                )
+            }
+            SolverError::Aborted => write!(f, "solver process ended without a result"),
+            SolverError::InvalidPermutation => write!(f, "column permutation is invalid"),
         }
-        Self { ffi: options }
     }
 }
 
-fn vec_of_array1_to_array2(columns: &Vec<Array1<f64>>) -> Array2<f64> {
+impl std::error::Error for SolverError {}
+
+pub(crate) fn vec_of_array1_to_array2<T: Copy>(columns: &[Array1<T>]) -> Array2<T> {
     let nrows = columns.first().map_or(0, |first_col| first_col.len());
     let ncols = columns.len();
-    let mut result = Array2::zeros((nrows, ncols));
+    Array2::from_shape_fn((nrows, ncols), |(row, col)| columns[col][row])
+}
 
-    for (col_idx, col) in columns.iter().enumerate() {
-        result.column_mut(col_idx).assign(col);
-    }
+pub(crate) fn columns_from_dense_data<T: Copy>(data: Vec<T>, n: usize) -> Vec<Array1<T>> {
+    data.chunks(n)
+        .map(|chunk| Array1::from_iter(chunk.iter().cloned()))
+        .collect()
+}
 
-    result
+/// Checks that `a x = b` is a square system with matching right-hand sides
+/// that SuperLU can safely be called on.
+pub(crate) fn check_system<T, I: SpIndex, Iptr: SpIndex>(
+    a: CsMatViewI<T, I, Iptr>,
+    b: &[Array1<T>],
+) -> Result<(), SolverError> {
+    check_system_rows(a, b.iter().map(|rhs_col| rhs_col.len()))
 }
 
-pub fn solve_super_lu(
-    a: CsMat<f64>,
-    b: &Vec<Array1<f64>>,
-    timeout: Option<Duration>,
-    options: &mut Options,
-) -> Result<Vec<Array1<f64>>, SolverError> {
+/// Like [`check_system`], given the lengths of the right-hand sides.
+pub(crate) fn check_system_rows<T, I: SpIndex, Iptr: SpIndex>(
+    a: CsMatViewI<T, I, Iptr>,
+    rhs_rows: impl IntoIterator<Item = usize>,
+) -> Result<(), SolverError> {
     let m = a.rows();
     let n = a.cols();
     if m != n {
-        return Err(SolverError::Conflict);
+        return Err(SolverError::DimensionMismatch {
+            expected: m,
+            found: n,
+        });
     }
-    if b.len() > 0 {
-        if m != b[0].len() {
-            return Err(SolverError::Conflict);
-        }
-        for rhs_col in b {
-            if rhs_col.len() != b[0].len() {
-                return Err(SolverError::Conflict);
-            }
+    for rows in rhs_rows {
+        if rows != m {
+            return Err(SolverError::DimensionMismatch {
+                expected: m,
+                found: rows,
+            });
         }
     }
     if a.nnz() == 0 {
-        return Err(SolverError::Unsolvable);
+        return Err(SolverError::Singular { column: 0 });
+    }
+    if let Some(column) = pattern::structurally_singular_column(a.view()) {
+        return Err(SolverError::Singular { column });
     }
+    Ok(())
+}
 
-    let a_mat = Arc::new(Mutex::new(SuperMatrix::from_csc_mat(a)));
-    let b_mat = Arc::new(Mutex::new(SuperMatrix::from_ndarray(
-        vec_of_array1_to_array2(b),
-    )));
-    let options = Arc::new(Mutex::new(options.ffi));
+/// Runs `task` on a worker thread and waits at most `timeout` for its result.
+/// Without a timeout, `task` simply runs on the calling thread.
+pub(crate) fn run_with_timeout<T, F>(timeout: Option<Duration>, task: F) -> Result<T, SolverError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, SolverError> + Send + 'static,
+{
+    let timeout_value = match timeout {
+        None => return task(),
+        Some(timeout_value) => timeout_value,
+    };
+    let (sender, receiver) = mpsc::channel();
 
-    let a_mat_clone = Arc::clone(&a_mat);
-    let b_mat_clone = Arc::clone(&b_mat);
-    let options_clone = Arc::clone(&options);
+    thread::spawn(move || {
+        let _ = sender.send(task());
+    });
+    match receiver.recv_timeout(timeout_value) {
+        Ok(res) => res,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(SolverError::Timeout),
+        Err(_) => {
+            panic!("Unknown internal SuperLU error");
+        }
+    }
+}
 
-    let (sender, receiver) = mpsc::channel();
+/// Solves `A x = b`, on a worker thread if a timeout is given and on the
+/// calling thread otherwise.
+///
+/// On timeout the worker is abandoned but keeps running to completion. Use
+/// [`solve_super_lu_in_process`] where a timeout must release the resources.
+pub fn solve_super_lu<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: &Vec<Array1<T>>,
+    timeout: Option<Duration>,
+    options: &mut Options,
+) -> Result<Vec<Array1<T>>, SolverError> {
+    run_with_timeout(timeout, prepare_gssv(a, b, options)?)
+}
 
-    thread::spawn(move || unsafe {
+/// Checks the system and copies it into SuperLU storage, returning the
+/// `?gssv` call to run on another thread.
+pub(crate) fn prepare_gssv<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: &[Array1<T>],
+    options: &Options,
+) -> Result<impl FnOnce() -> Result<Vec<Array1<T>>, SolverError> + Send + 'static, SolverError> {
+    check_system(a.view(), b)?;
+    let perm_c_init = options.initial_perm_c(a.cols())?;
+
+    let mut a_mat = SuperMatrix::from_cs_mat(transposed(a, options.ffi.Trans));
+    let b_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    let mut options = options.ffi;
+
+    Ok(move || gssv(&mut a_mat, b_mat, &perm_c_init, &mut options))
+}
+
+/// `?gssv` ignores `Trans`, so `A^T` or `A^H` is handed to it instead.
+/// For `TRANS` this only relabels the storage, CSC as CSR and vice versa.
+/// `CONJ` also conjugates the values, which copies them.
+pub(crate) fn transposed<T: SuperLuScalar>(a: CsMat<T>, trans: ffi::trans_t) -> CsMat<T> {
+    match trans {
+        ffi::trans_t::NOTRANS => a,
+        ffi::trans_t::TRANS => a.transpose_into(),
+        ffi::trans_t::CONJ => a.transpose_into().map(|v| v.conj()),
+    }
+}
+
+/// Solves `A x = b` for a borrowed CSC or CSR matrix without copying it.
+///
+/// Indices are only copied if they are not `c_int`, and the index pointer
+/// only if the view is a slice of a larger matrix. Values are only copied to
+/// conjugate them for `Trans = CONJ`. Since `A` is borrowed, the solve runs
+/// on the calling thread and cannot time out.
+pub fn solve_super_lu_view<T: SuperLuScalar, I: SpIndex + 'static, Iptr: SpIndex + 'static>(
+    a: CsMatViewI<'_, T, I, Iptr>,
+    b: &[Array1<T>],
+    options: &mut Options,
+) -> Result<Vec<Array1<T>>, SolverError> {
+    check_system(a.view(), b)?;
+    let perm_c_init = options.initial_perm_c(a.cols())?;
+
+    let conjugated;
+    let mut a_mat = match options.ffi.Trans {
+        ffi::trans_t::NOTRANS => SuperMatrix::from_cs_view(a),
+        ffi::trans_t::TRANS => SuperMatrix::from_cs_view(a.transpose_view()),
+        ffi::trans_t::CONJ => {
+            conjugated = a.transpose_view().map(|v| v.conj());
+            SuperMatrix::from_cs_view(conjugated.view())
+        }
+    };
+    let b_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    let mut options = options.ffi;
+    gssv(&mut a_mat, b_mat, &perm_c_init, &mut options)
+}
+
+fn gssv<T: SuperLuScalar>(
+    a_mat: &mut SuperMatrix,
+    mut b_mat: SuperMatrix,
+    perm_c_init: &[c_int],
+    options: &mut ffi::superlu_options_t,
+) -> Result<Vec<Array1<T>>, SolverError> {
+    let n = a_mat.ncols();
+    let info = gssv_in_place::<T>(a_mat, &mut b_mat, perm_c_init, options);
+    if info != 0 {
+        return Err(SolverError::from_info(info, n));
+    }
+    match b_mat.dense_values() {
+        None => Err(SolverError::Unsolvable),
+        Some(data) => Ok(columns_from_dense_data(data, n)),
+    }
+}
+
+/// Runs `?gssv`, overwriting `b_mat` with the solution, and returns its
+/// `info`.
+pub(crate) fn gssv_in_place<T: SuperLuScalar>(
+    a_mat: &mut SuperMatrix,
+    b_mat: &mut SuperMatrix,
+    perm_c_init: &[c_int],
+    options: &mut ffi::superlu_options_t,
+) -> c_int {
+    let m = a_mat.nrows();
+    let n = a_mat.ncols();
+    unsafe {
         let perm_r = ffi::intMalloc(m as c_int);
         assert!(!perm_r.is_null());
 
         let perm_c = ffi::intMalloc(n as c_int);
         assert!(!perm_c.is_null());
-
-        ffi::set_default_options(&mut *options_clone.lock().unwrap());
+        // Only read by ?gssv for `ColPerm = MY_PERMC`.
+        from_raw_parts_mut(perm_c, n).copy_from_slice(perm_c_init);
 
         let mut stat: ffi::SuperLUStat_t = mem::zeroed();
         ffi::StatInit(&mut stat);
@@ -98,232 +318,27 @@ pub fn solve_super_lu(
         let mut u_mat: ffi::SuperMatrix = mem::zeroed();
 
         let mut info = 0;
-        ffi::dgssv(
-            &mut *options_clone.lock().unwrap(),
-            a_mat_clone.lock().unwrap().raw_mut(),
+        T::gssv(
+            options,
+            a_mat.raw_mut(),
             perm_c,
             perm_r,
             &mut l_mat,
             &mut u_mat,
-            b_mat_clone.lock().unwrap().raw_mut(),
+            b_mat.raw_mut(),
             &mut stat,
             &mut info,
         );
 
         ffi::SUPERLU_FREE(perm_r as *mut _);
         ffi::SUPERLU_FREE(perm_c as *mut _);
-        ffi::Destroy_SuperNode_Matrix(&mut l_mat);
-        ffi::Destroy_CompCol_Matrix(&mut u_mat);
-        ffi::StatFree(&mut stat);
-
-        if info != 0 {
-            let _ = sender.send(Err(SolverError::Unsolvable));
-        } else {
-            let _ = sender.send(Ok(()));
-        }
-    });
-    match timeout {
-        None => match receiver.recv() {
-            Ok(res) => match res {
-                Ok(_) => {
-                    let res_data = b_mat.lock().unwrap().raw().data_to_vec();
-                    match res_data {
-                        None => Err(SolverError::Unsolvable),
-                        Some(data) => Ok(data
-                            .chunks(n)
-                            .map(|chunk| Array1::from_iter(chunk.iter().cloned()))
-                            .collect()),
-                    }
-                }
-                Err(_) => Err(SolverError::Unsolvable),
-            },
-            Err(_) => {
-                panic!("Unknown internal SuperLU error");
-            }
-        },
-        Some(timeout_value) => match receiver.recv_timeout(timeout_value) {
-            Ok(res) => match res {
-                Ok(_) => {
-                    let res_data = b_mat.lock().unwrap().raw().data_to_vec();
-                    match res_data {
-                        None => Err(SolverError::Unsolvable),
-                        Some(data) => Ok(data
-                            .chunks(n)
-                            .map(|chunk| Array1::from_iter(chunk.iter().cloned()))
-                            .collect()),
-                    }
-                }
-                Err(_) => Err(SolverError::Unsolvable),
-            },
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                return Err(SolverError::Timeout);
-            }
-            Err(_) => {
-                panic!("Unknown internal SuperLU error");
-            }
-        },
-    }
-}
-
-pub struct SuperMatrix {
-    raw: ffi::SuperMatrix,
-    rust_managed: bool,
-}
-
-pub trait FromSuperMatrix: Sized {
-    fn from_super_matrix(_: &SuperMatrix) -> Option<Self>;
-}
-
-unsafe impl Send for SuperMatrix {}
-
-impl SuperMatrix {
-    pub unsafe fn from_raw(raw: ffi::SuperMatrix) -> SuperMatrix {
-        SuperMatrix {
-            raw,
-            rust_managed: false,
-        }
-    }
-
-    pub fn into_raw(self) -> ffi::SuperMatrix {
-        let raw = self.raw;
-        if self.rust_managed {
-            mem::forget(self);
-        }
-        raw
-    }
-
-    pub fn from_csc_mat(mat: CsMat<f64>) -> Self {
-        assert_eq!(mat.storage(), sprs::CompressedStorage::CSC);
-
-        let m = mat.rows() as c_int;
-        let n = mat.cols() as c_int;
-        let nnz = mat.nnz() as c_int;
-
-        let mut raw: ffi::SuperMatrix = unsafe { mem::zeroed() };
-
-        let nzval: Vec<c_double> = mat.data().iter().map(|&x| x as c_double).collect();
-        let rowind: Vec<c_int> = mat.indices().iter().map(|&x| x as c_int).collect();
-        let mut colptr = Vec::new();
-        let colptr_raw = mat.indptr();
-        for ptr in colptr_raw.as_slice().unwrap() {
-            colptr.push(ptr.clone() as c_int)
-        }
-
-        let nzval_boxed = nzval.into_boxed_slice();
-        let rowind_boxed = rowind.into_boxed_slice();
-        let colptr_boxed = colptr.into_boxed_slice();
-
-        let nzval_ptr = Box::leak(nzval_boxed).as_mut_ptr();
-        let rowind_ptr = Box::leak(rowind_boxed).as_mut_ptr();
-        let colptr_ptr = Box::leak(colptr_boxed).as_mut_ptr();
-
-        unsafe {
-            ffi::dCreate_CompCol_Matrix(
-                &mut raw,
-                m,
-                n,
-                nnz,
-                nzval_ptr as *mut c_double,
-                rowind_ptr as *mut c_int,
-                colptr_ptr as *mut c_int,
-                Stype_t::SLU_NC,
-                Dtype_t::SLU_D,
-                Mtype_t::SLU_GE,
-            );
-        }
-        unsafe { Self::from_raw(raw) }
-    }
-
-    pub fn from_ndarray(array: Array2<f64>) -> Self {
-        let nrows = array.nrows() as c_int;
-        let ncols = array.ncols() as c_int;
-
-        let col_major_data = unsafe { ffi::doubleMalloc(ncols * nrows) };
-        let mut index: usize = 0;
-        let col_major_data_ptr =
-            unsafe { from_raw_parts_mut(col_major_data, (ncols * nrows) as usize) };
-        for col in 0..ncols as usize {
-            for row in 0..nrows as usize {
-                col_major_data_ptr[index] = array[[row, col]];
-                index += 1;
-            }
-        }
-
-        let mut raw: ffi::SuperMatrix = unsafe { std::mem::zeroed() };
-
-        unsafe {
-            ffi::dCreate_Dense_Matrix(
-                &mut raw as *mut ffi::SuperMatrix,
-                nrows,
-                ncols,
-                col_major_data,
-                nrows,
-                Stype_t::SLU_DN,
-                Dtype_t::SLU_D,
-                Mtype_t::SLU_GE,
-            );
-
-            SuperMatrix {
-                raw,
-                rust_managed: true,
-            }
-        }
-    }
-
-    pub fn into_ndarray(self) -> Option<Array2<f64>> {
-        match self.raw.data_to_vec() {
-            None => None,
-            Some(data) => match Array2::from_shape_vec((self.nrows(), self.ncols()), data) {
-                Ok(arr) => Some(arr.t().to_owned()),
-                Err(_) => None,
-            },
-        }
-    }
-
-    pub fn nrows(&self) -> usize {
-        self.raw.nrow as usize
-    }
-
-    pub fn ncols(&self) -> usize {
-        self.raw.ncol as usize
-    }
-
-    pub fn raw(&self) -> &ffi::SuperMatrix {
-        &self.raw
-    }
-
-    pub fn raw_mut(&mut self) -> *mut ffi::SuperMatrix {
-        &mut self.raw
-    }
-}
-
-impl Drop for SuperMatrix {
-    fn drop(&mut self) {
-        unsafe {
-            let store = &*(self.raw().Store as *const ffi::NCformat);
-            if store.nnz == 0 {
-                return;
-            }
-        }
-        if self.rust_managed {
-            match self.raw.Stype {
-                Stype_t::SLU_NC => unsafe {
-                    ffi::Destroy_CompCol_Matrix(&mut self.raw);
-                },
-                Stype_t::SLU_NCP => unsafe {
-                    ffi::Destroy_CompCol_Permuted(&mut self.raw);
-                },
-                Stype_t::SLU_NR => unsafe {
-                    ffi::Destroy_CompRow_Matrix(&mut self.raw);
-                },
-                Stype_t::SLU_SC | ffi::Stype_t::SLU_SCP | ffi::Stype_t::SLU_SR => unsafe {
-                    ffi::Destroy_SuperNode_Matrix(&mut self.raw);
-                },
-                Stype_t::SLU_DN => unsafe {
-                    ffi::Destroy_Dense_Matrix(&mut self.raw);
-                },
-                _ => {}
-            }
+        // L and U are only allocated once the factorization has run, which
+        // is not the case for invalid arguments or failed allocations.
+        if info >= 0 && info as usize <= n {
+            ffi::Destroy_SuperNode_Matrix(&mut l_mat);
+            ffi::Destroy_CompCol_Matrix(&mut u_mat);
         }
+        ffi::StatFree(&mut stat);
+        info
     }
 }
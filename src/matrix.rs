@@ -0,0 +1,660 @@
+//! Safe wrappers around SuperLU matrices, one per storage format.
+
+use crate::bindings::{NCPformat, NRformat};
+use crate::SuperLuScalar;
+use libc::c_int;
+use ndarray::{Array2, ArrayViewMut2};
+use sprs::{CsMat, CsMatViewI, SpIndex};
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::{mem, slice};
+use superlu_sys as ffi;
+use superlu_sys::{DNformat, NCformat, SCformat, Stype_t};
+
+type Destroy = unsafe extern "C" fn(*mut ffi::SuperMatrix);
+
+/// Who frees the arrays a matrix points into.
+enum Ownership<'a> {
+    /// Someone else, nothing is freed on drop.
+    Foreign,
+    /// SuperLU, the whole matrix is freed with the given `Destroy_*` routine.
+    SuperLu(Destroy),
+    /// Rust, only the store itself was allocated by SuperLU. The arrays are
+    /// held here until then.
+    Buffers(#[allow(dead_code)] Box<dyn Send + 'a>),
+    /// Rust, the arrays are borrowed for `'a`. Only the store itself was
+    /// allocated by SuperLU.
+    Borrowed(PhantomData<&'a mut ()>),
+}
+
+struct Handle<'a> {
+    raw: ffi::SuperMatrix,
+    ownership: Ownership<'a>,
+}
+
+unsafe impl Send for Handle<'_> {}
+
+impl Drop for Handle<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            match self.ownership {
+                Ownership::Foreign => {}
+                Ownership::SuperLu(destroy) => destroy(&mut self.raw),
+                Ownership::Buffers(_) | Ownership::Borrowed(_) => {
+                    ffi::Destroy_SuperMatrix_Store(&mut self.raw)
+                }
+            }
+        }
+    }
+}
+
+/// Slice of `len` elements at `ptr`, which may be null if `len` is zero.
+unsafe fn slice_from<'s, T>(ptr: *const T, len: usize) -> &'s [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+macro_rules! impl_matrix_common {
+    ($name:ident $(<$lt:lifetime>)?, $stype:ident, $store:ty) => {
+        impl $(<$lt>)? $name $(<$lt>)? {
+            /// # Safety
+            ///
+            #[doc = concat!("`raw` must be a valid `", stringify!($stype), "` matrix.")]
+            /// Its storage is not freed on drop.
+            pub unsafe fn from_raw(raw: ffi::SuperMatrix) -> Self {
+                debug_assert!(raw.Stype == Stype_t::$stype);
+                Self {
+                    handle: Handle {
+                        raw,
+                        ownership: Ownership::Foreign,
+                    },
+                }
+            }
+
+            pub fn nrows(&self) -> usize {
+                self.handle.raw.nrow as usize
+            }
+
+            pub fn ncols(&self) -> usize {
+                self.handle.raw.ncol as usize
+            }
+
+            pub fn raw(&self) -> &ffi::SuperMatrix {
+                &self.handle.raw
+            }
+
+            pub fn raw_mut(&mut self) -> *mut ffi::SuperMatrix {
+                &mut self.handle.raw
+            }
+
+            /// Gives up ownership, the storage is no longer freed on drop.
+            pub fn into_raw(self) -> ffi::SuperMatrix {
+                let raw = self.handle.raw;
+                mem::forget(self);
+                raw
+            }
+
+            fn store(&self) -> &$store {
+                unsafe { &*(self.handle.raw.Store as *const $store) }
+            }
+
+            fn values_of<T: SuperLuScalar>(&self, len: usize) -> Option<&[T]> {
+                if self.handle.raw.Dtype != T::DTYPE {
+                    return None;
+                }
+                Some(unsafe { slice_from(self.store().nzval as *const T, len) })
+            }
+        }
+    };
+}
+
+/// Sparse matrix in compressed column format (`SLU_NC`).
+pub struct CompColMatrix<'a> {
+    handle: Handle<'a>,
+}
+
+impl_matrix_common!(CompColMatrix<'a>, SLU_NC, NCformat);
+
+impl CompColMatrix<'static> {
+    /// # Safety
+    ///
+    /// `raw` must be a `SLU_NC` matrix allocated by SuperLU.
+    pub(crate) unsafe fn from_superlu(raw: ffi::SuperMatrix) -> Self {
+        Self {
+            handle: Handle {
+                raw,
+                ownership: Ownership::SuperLu(ffi::Destroy_CompCol_Matrix),
+            },
+        }
+    }
+}
+
+impl<'a> CompColMatrix<'a> {
+    pub fn nnz(&self) -> usize {
+        self.store().nnz as usize
+    }
+
+    /// Start of each column in `rowind` and `nzval`, followed by `nnz`.
+    pub fn colptr(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().colptr, self.ncols() + 1) }
+    }
+
+    pub fn rowind(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().rowind, self.nnz()) }
+    }
+
+    /// The values, if they are of type `T`.
+    pub fn nzval<T: SuperLuScalar>(&self) -> Option<&[T]> {
+        self.values_of(self.nnz())
+    }
+}
+
+/// Sparse matrix in compressed row format (`SLU_NR`).
+pub struct CompRowMatrix<'a> {
+    handle: Handle<'a>,
+}
+
+impl_matrix_common!(CompRowMatrix<'a>, SLU_NR, NRformat);
+
+impl<'a> CompRowMatrix<'a> {
+    pub fn nnz(&self) -> usize {
+        self.store().nnz as usize
+    }
+
+    /// Start of each row in `colind` and `nzval`, followed by `nnz`.
+    pub fn rowptr(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().rowptr, self.nrows() + 1) }
+    }
+
+    pub fn colind(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().colind, self.nnz()) }
+    }
+
+    /// The values, if they are of type `T`.
+    pub fn nzval<T: SuperLuScalar>(&self) -> Option<&[T]> {
+        self.values_of(self.nnz())
+    }
+}
+
+/// Dense matrix stored column by column (`SLU_DN`).
+pub struct DenseMatrix<'a> {
+    handle: Handle<'a>,
+}
+
+impl_matrix_common!(DenseMatrix<'a>, SLU_DN, DNformat);
+
+impl DenseMatrix<'static> {
+    /// Copies `array` into a matrix allocated by SuperLU.
+    pub fn from_ndarray<T: SuperLuScalar>(array: Array2<T>) -> Self {
+        let nrows = array.nrows();
+        let ncols = array.ncols();
+
+        let mut raw: ffi::SuperMatrix = unsafe { mem::zeroed() };
+        unsafe {
+            let col_major_data = T::malloc((nrows * ncols) as c_int);
+            if nrows * ncols > 0 {
+                assert!(!col_major_data.is_null());
+                let values = slice::from_raw_parts_mut(col_major_data, nrows * ncols);
+                for ((row, col), &value) in array.indexed_iter() {
+                    values[col * nrows + row] = value;
+                }
+            }
+            T::create_dense_matrix(
+                &mut raw,
+                nrows as c_int,
+                ncols as c_int,
+                col_major_data,
+                nrows as c_int,
+            );
+        }
+        Self {
+            handle: Handle {
+                raw,
+                ownership: Ownership::SuperLu(ffi::Destroy_Dense_Matrix),
+            },
+        }
+    }
+}
+
+impl<'a> DenseMatrix<'a> {
+    /// Builds a matrix pointing into `view`, which SuperLU may then overwrite.
+    ///
+    /// Returns `None` unless `view` is stored column by column, with
+    /// contiguous columns any distance apart, as for a Fortran-ordered array
+    /// or a slice of its rows.
+    pub fn from_view_mut<T: SuperLuScalar>(mut view: ArrayViewMut2<'a, T>) -> Option<Self> {
+        let (nrows, ncols) = view.dim();
+        let (row_stride, col_stride) = (view.strides()[0], view.strides()[1]);
+        if nrows > 1 && row_stride != 1 {
+            return None;
+        }
+        let lda = if ncols > 1 {
+            if col_stride < nrows.max(1) as isize {
+                return None;
+            }
+            col_stride as usize
+        } else {
+            nrows.max(1)
+        };
+
+        let mut raw: ffi::SuperMatrix = unsafe { mem::zeroed() };
+        unsafe {
+            T::create_dense_matrix(
+                &mut raw,
+                nrows as c_int,
+                ncols as c_int,
+                view.as_mut_ptr(),
+                lda as c_int,
+            );
+        }
+        Some(Self {
+            handle: Handle {
+                raw,
+                ownership: Ownership::Borrowed(PhantomData),
+            },
+        })
+    }
+
+    /// Leading dimension, the distance between the starts of two columns.
+    pub fn lda(&self) -> usize {
+        self.store().lda as usize
+    }
+
+    /// The stored values from the first to the last entry, if they are of
+    /// type `T`. Columns start `lda` apart.
+    pub fn values<T: SuperLuScalar>(&self) -> Option<&[T]> {
+        self.values_of(self.values_len())
+    }
+
+    /// The stored values from the first to the last entry, if they are of
+    /// type `T`. Columns start `lda` apart.
+    pub fn values_mut<T: SuperLuScalar>(&mut self) -> Option<&mut [T]> {
+        if self.handle.raw.Dtype != T::DTYPE {
+            return None;
+        }
+        let len = self.values_len();
+        if len == 0 {
+            return Some(&mut []);
+        }
+        Some(unsafe { slice::from_raw_parts_mut(self.store().nzval as *mut T, len) })
+    }
+
+    /// The padding after the last column may lie outside of a borrowed array.
+    fn values_len(&self) -> usize {
+        match self.ncols() {
+            0 => 0,
+            ncols => self.lda() * (ncols - 1) + self.nrows(),
+        }
+    }
+
+    /// Copies the values column by column, skipping the padding up to `lda`.
+    pub(crate) fn column_major_values<T: SuperLuScalar>(&self) -> Option<Vec<T>> {
+        let (nrows, lda) = (self.nrows(), self.lda());
+        let values = self.values::<T>()?;
+        if nrows == lda {
+            return Some(values.to_vec());
+        }
+        Some(
+            values
+                .chunks(lda)
+                .flat_map(|column| column[..nrows].iter().copied())
+                .collect(),
+        )
+    }
+
+    pub fn to_ndarray<T: SuperLuScalar>(&self) -> Option<Array2<T>> {
+        let lda = self.lda();
+        let values = self.values::<T>()?;
+        Some(Array2::from_shape_fn(
+            (self.nrows(), self.ncols()),
+            |(row, col)| values[col * lda + row],
+        ))
+    }
+}
+
+/// Supernodal matrix (`SLU_SC`), the format of the `L` factor.
+pub struct SupernodeMatrix {
+    handle: Handle<'static>,
+}
+
+impl_matrix_common!(SupernodeMatrix, SLU_SC, SCformat);
+
+impl SupernodeMatrix {
+    /// # Safety
+    ///
+    /// `raw` must be a `SLU_SC` matrix allocated by SuperLU.
+    pub(crate) unsafe fn from_superlu(raw: ffi::SuperMatrix) -> Self {
+        Self {
+            handle: Handle {
+                raw,
+                ownership: Ownership::SuperLu(ffi::Destroy_SuperNode_Matrix),
+            },
+        }
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.store().nnz as usize
+    }
+
+    pub fn supernode_count(&self) -> usize {
+        // `nsuper` is the index of the last supernode.
+        if self.ncols() == 0 {
+            0
+        } else {
+            self.store().nsuper as usize + 1
+        }
+    }
+
+    /// First column of each supernode, followed by `ncols`.
+    pub fn sup_to_col(&self) -> &[c_int] {
+        let len = if self.ncols() == 0 {
+            0
+        } else {
+            self.supernode_count() + 1
+        };
+        unsafe { slice_from(self.store().sup_to_col, len) }
+    }
+
+    /// Supernode of each column.
+    pub fn col_to_sup(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().col_to_sup, self.ncols()) }
+    }
+
+    /// Start of each column in `nzval`, followed by the number of values.
+    /// The columns of a supernode are stored as one dense block.
+    pub fn nzval_colptr(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().nzval_colptr, self.ncols() + 1) }
+    }
+
+    /// Start of each supernode in `rowind`, indexed by its first column and
+    /// followed by the number of row indices.
+    pub fn rowind_colptr(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().rowind_colptr, self.ncols() + 1) }
+    }
+
+    /// Row indices of each supernode, shared by all of its columns.
+    pub fn rowind(&self) -> &[c_int] {
+        let len = self.rowind_colptr().last().map_or(0, |&l| l as usize);
+        unsafe { slice_from(self.store().rowind, len) }
+    }
+
+    /// The values, if they are of type `T`.
+    pub fn nzval<T: SuperLuScalar>(&self) -> Option<&[T]> {
+        let len = self.nzval_colptr().last().map_or(0, |&l| l as usize);
+        self.values_of(len)
+    }
+
+    /// Row indices and values of column `j`, if the values are of type `T`.
+    ///
+    /// The rows are those of the supernode containing `j`, so the entries
+    /// above the diagonal belong to `U`.
+    pub fn column<T: SuperLuScalar>(&self, j: usize) -> Option<(&[c_int], &[T])> {
+        let first_col = self.sup_to_col()[self.col_to_sup()[j] as usize] as usize;
+        let rowind_colptr = self.rowind_colptr();
+        let rows = &self.rowind()
+            [rowind_colptr[first_col] as usize..rowind_colptr[first_col + 1] as usize];
+        let nzval_colptr = self.nzval_colptr();
+        let values = &self.nzval()?[nzval_colptr[j] as usize..nzval_colptr[j + 1] as usize];
+        Some((rows, values))
+    }
+}
+
+/// Compressed column matrix with permuted columns (`SLU_NCP`), as created by
+/// `sp_preorder`. The row indices and values belong to the original matrix.
+pub struct PermutedCompColMatrix {
+    handle: Handle<'static>,
+}
+
+impl_matrix_common!(PermutedCompColMatrix, SLU_NCP, NCPformat);
+
+impl PermutedCompColMatrix {
+    /// # Safety
+    ///
+    /// `raw` must be a `SLU_NCP` matrix created by `sp_preorder`.
+    pub(crate) unsafe fn from_superlu(raw: ffi::SuperMatrix) -> Self {
+        Self {
+            handle: Handle {
+                raw,
+                ownership: Ownership::SuperLu(ffi::Destroy_CompCol_Permuted),
+            },
+        }
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.store().nnz as usize
+    }
+
+    /// Start of each permuted column in `rowind` and `nzval`.
+    pub fn colbeg(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().colbeg, self.ncols()) }
+    }
+
+    /// End of each permuted column in `rowind` and `nzval`.
+    pub fn colend(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().colend, self.ncols()) }
+    }
+
+    pub fn rowind(&self) -> &[c_int] {
+        unsafe { slice_from(self.store().rowind, self.nnz()) }
+    }
+
+    /// The values, if they are of type `T`.
+    pub fn nzval<T: SuperLuScalar>(&self) -> Option<&[T]> {
+        self.values_of(self.nnz())
+    }
+}
+
+/// A SuperLU matrix in any of the supported storage formats.
+pub enum SuperMatrix<'a> {
+    CompCol(CompColMatrix<'a>),
+    CompRow(CompRowMatrix<'a>),
+    Dense(DenseMatrix<'a>),
+    Supernode(SupernodeMatrix),
+    PermutedCompCol(PermutedCompColMatrix),
+}
+
+pub trait FromSuperMatrix: Sized {
+    fn from_super_matrix(_: &SuperMatrix<'_>) -> Option<Self>;
+}
+
+impl SuperMatrix<'static> {
+    /// # Safety
+    ///
+    /// `raw` must be a valid SuperLU matrix. Its storage is not freed on drop.
+    ///
+    /// # Panics
+    ///
+    /// If `raw` is stored in a format without a wrapper, such as `SLU_SCP`.
+    pub unsafe fn from_raw(raw: ffi::SuperMatrix) -> SuperMatrix<'static> {
+        match raw.Stype {
+            Stype_t::SLU_NC => SuperMatrix::CompCol(CompColMatrix::from_raw(raw)),
+            Stype_t::SLU_NR => SuperMatrix::CompRow(CompRowMatrix::from_raw(raw)),
+            Stype_t::SLU_DN => SuperMatrix::Dense(DenseMatrix::from_raw(raw)),
+            Stype_t::SLU_SC => SuperMatrix::Supernode(SupernodeMatrix::from_raw(raw)),
+            Stype_t::SLU_NCP => SuperMatrix::PermutedCompCol(PermutedCompColMatrix::from_raw(raw)),
+            _ => panic!("Unsupported SuperLU storage format"),
+        }
+    }
+
+    /// Builds an `SLU_NC` matrix. CSR input is converted to CSC first.
+    pub fn from_csc_mat<T: SuperLuScalar>(mat: CsMat<T>) -> Self {
+        let mat = if mat.is_csc() { mat } else { mat.to_csc() };
+        Self::from_owned_compressed(mat)
+    }
+
+    /// Builds an `SLU_NR` matrix. CSC input is converted to CSR first.
+    ///
+    /// The drivers `?gssv` and `?gssvx` accept this format directly by
+    /// factoring the transpose, the factorization routines do not.
+    pub fn from_csr_mat<T: SuperLuScalar>(mat: CsMat<T>) -> Self {
+        let mat = if mat.is_csr() { mat } else { mat.to_csr() };
+        Self::from_owned_compressed(mat)
+    }
+
+    /// Takes over the values of `mat`, only its indices are converted.
+    fn from_owned_compressed<T: SuperLuScalar>(mat: CsMat<T>) -> Self {
+        let (m, n, is_csc) = (mat.rows(), mat.cols(), mat.is_csc());
+        let (indptr, indices, data) = mat.into_raw_storage();
+        let start = indptr.first().copied().unwrap_or(0);
+        let indptr = indptr.iter().map(|&i| (i - start) as c_int).collect();
+        let indices = indices.iter().map(|&i| i as c_int).collect();
+        SuperMatrix::from_compressed_parts(
+            (m, n),
+            is_csc,
+            Cow::Owned(data),
+            Cow::Owned(indices),
+            Cow::Owned(indptr),
+        )
+    }
+
+    /// Builds an `SLU_NC` or `SLU_NR` matrix matching the storage of `mat`.
+    pub fn from_cs_mat<T: SuperLuScalar>(mat: CsMat<T>) -> Self {
+        if mat.is_csc() {
+            Self::from_csc_mat(mat)
+        } else {
+            Self::from_csr_mat(mat)
+        }
+    }
+
+    /// Copies `array` into storage allocated by SuperLU. See
+    /// [`SuperMatrix::from_ndarray_view_mut`] to use the array's own storage.
+    pub fn from_ndarray<T: SuperLuScalar>(array: Array2<T>) -> Self {
+        SuperMatrix::Dense(DenseMatrix::from_ndarray(array))
+    }
+}
+
+impl<'a> SuperMatrix<'a> {
+    /// Builds an `SLU_NC` or `SLU_NR` matrix pointing into the arrays of
+    /// `view`, matching its storage.
+    ///
+    /// Indices are converted if they are not `c_int`, and the index pointer
+    /// is rebased if `view` is an outer slice of a larger matrix. SuperLU
+    /// must not write to the resulting matrix, which rules out equilibration.
+    pub fn from_cs_view<T: SuperLuScalar, I: SpIndex + 'static, Iptr: SpIndex + 'static>(
+        view: CsMatViewI<'a, T, I, Iptr>,
+    ) -> Self {
+        let (m, n, is_csc) = (view.rows(), view.cols(), view.is_csc());
+        let (indptr, indices, data) = view.into_raw_storage();
+
+        // The indices and data of an outer slice are sliced as well, only its
+        // index pointer still counts from the start of the full matrix.
+        let start = indptr.first().map_or(0, |i| i.index());
+        let indptr = if start == 0 {
+            c_int_indices(indptr)
+        } else {
+            Cow::Owned(
+                indptr
+                    .iter()
+                    .map(|&i| (i.index() - start) as c_int)
+                    .collect(),
+            )
+        };
+        let indices = c_int_indices(indices);
+        Self::from_compressed_parts((m, n), is_csc, Cow::Borrowed(data), indices, indptr)
+    }
+
+    /// Builds an `SLU_DN` matrix pointing into `view` without copying, see
+    /// [`DenseMatrix::from_view_mut`].
+    pub fn from_ndarray_view_mut<T: SuperLuScalar>(view: ArrayViewMut2<'a, T>) -> Option<Self> {
+        DenseMatrix::from_view_mut(view).map(SuperMatrix::Dense)
+    }
+
+    fn from_compressed_parts<T: SuperLuScalar>(
+        (m, n): (usize, usize),
+        is_csc: bool,
+        data: Cow<'a, [T]>,
+        indices: Cow<'a, [c_int]>,
+        indptr: Cow<'a, [c_int]>,
+    ) -> Self {
+        let mut raw: ffi::SuperMatrix = unsafe { mem::zeroed() };
+        unsafe {
+            let create = if is_csc {
+                T::create_comp_col_matrix
+            } else {
+                T::create_comp_row_matrix
+            };
+            create(
+                &mut raw,
+                m as c_int,
+                n as c_int,
+                indices.len() as c_int,
+                data.as_ptr() as *mut T,
+                indices.as_ptr() as *mut c_int,
+                indptr.as_ptr() as *mut c_int,
+            );
+        }
+        let handle = Handle {
+            raw,
+            ownership: Ownership::Buffers(Box::new((data, indices, indptr))),
+        };
+        if is_csc {
+            SuperMatrix::CompCol(CompColMatrix { handle })
+        } else {
+            SuperMatrix::CompRow(CompRowMatrix { handle })
+        }
+    }
+
+    pub fn into_ndarray<T: SuperLuScalar>(self) -> Option<Array2<T>> {
+        match &self {
+            SuperMatrix::Dense(dense) => dense.to_ndarray(),
+            _ => None,
+        }
+    }
+
+    /// Copies the values of a dense matrix stored with element type `T`.
+    pub(crate) fn dense_values<T: SuperLuScalar>(&self) -> Option<Vec<T>> {
+        match self {
+            SuperMatrix::Dense(dense) => dense.column_major_values(),
+            _ => None,
+        }
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.raw().nrow as usize
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.raw().ncol as usize
+    }
+
+    pub fn raw(&self) -> &ffi::SuperMatrix {
+        match self {
+            SuperMatrix::CompCol(mat) => mat.raw(),
+            SuperMatrix::CompRow(mat) => mat.raw(),
+            SuperMatrix::Dense(mat) => mat.raw(),
+            SuperMatrix::Supernode(mat) => mat.raw(),
+            SuperMatrix::PermutedCompCol(mat) => mat.raw(),
+        }
+    }
+
+    pub fn raw_mut(&mut self) -> *mut ffi::SuperMatrix {
+        match self {
+            SuperMatrix::CompCol(mat) => mat.raw_mut(),
+            SuperMatrix::CompRow(mat) => mat.raw_mut(),
+            SuperMatrix::Dense(mat) => mat.raw_mut(),
+            SuperMatrix::Supernode(mat) => mat.raw_mut(),
+            SuperMatrix::PermutedCompCol(mat) => mat.raw_mut(),
+        }
+    }
+
+    /// Gives up ownership, the storage is no longer freed on drop.
+    pub fn into_raw(self) -> ffi::SuperMatrix {
+        let raw = *self.raw();
+        mem::forget(self);
+        raw
+    }
+}
+
+/// Reinterprets `indices` as `c_int`, copying only if `I` is another type.
+fn c_int_indices<I: SpIndex + 'static>(indices: &[I]) -> Cow<'_, [c_int]> {
+    if TypeId::of::<I>() == TypeId::of::<c_int>() {
+        let ptr = indices.as_ptr() as *const c_int;
+        Cow::Borrowed(unsafe { slice::from_raw_parts(ptr, indices.len()) })
+    } else {
+        Cow::Owned(indices.iter().map(|&i| i.index() as c_int).collect())
+    }
+}
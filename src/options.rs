@@ -0,0 +1,350 @@
+use crate::SolverError;
+use libc::c_int;
+use std::mem;
+use std::ops::BitOr;
+use superlu_sys as ffi;
+
+/// SuperLU options, handed to the SuperLU drivers as they are.
+///
+/// The `ILU_*` settings only affect [`crate::IncompleteLu`]. They default to
+/// the values of `ilu_set_default_options`.
+#[derive(Clone)]
+pub struct Options {
+    pub ffi: ffi::superlu_options_t,
+    /// Column permutation used when `ffi.ColPerm` is `MY_PERMC`. Without one,
+    /// `MY_PERMC` means the natural ordering. It is checked before every use,
+    /// since SuperLU indexes its work arrays with it.
+    pub perm_c: Option<Vec<c_int>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        let mut options: ffi::superlu_options_t = unsafe { mem::zeroed() };
+        unsafe {
+            ffi::set_default_options(&mut options);
+        }
+        options.ILU_DropRule = DropRule::default().0;
+        options.ILU_DropTol = 1e-4;
+        options.ILU_FillFactor = 10.0;
+        options.ILU_Norm = ffi::norm_t::INF_NORM;
+        options.ILU_MILU = ffi::milu_t::SILU;
+        options.ILU_MILU_Dim = 3.0;
+        options.ILU_FillTol = 1e-2;
+        Self {
+            ffi: options,
+            perm_c: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// Initial column permutation for an `n` by `n` matrix. Only differs from
+    /// the natural ordering for `MY_PERMC` with a custom permutation.
+    pub(crate) fn initial_perm_c(&self, n: usize) -> Result<Vec<c_int>, SolverError> {
+        match (&self.ffi.ColPerm, &self.perm_c) {
+            (ffi::colperm_t::MY_PERMC, Some(perm_c)) => {
+                if perm_c.len() != n {
+                    return Err(SolverError::DimensionMismatch {
+                        expected: n,
+                        found: perm_c.len(),
+                    });
+                }
+                if !is_permutation(perm_c) {
+                    return Err(SolverError::InvalidPermutation);
+                }
+                Ok(perm_c.clone())
+            }
+            _ => Ok((0..n as c_int).collect()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnPermutation {
+    Natural,
+    /// Minimum degree ordering on the structure of `A^T * A`.
+    MmdAtA,
+    /// Minimum degree ordering on the structure of `A^T + A`.
+    MmdAtPlusA,
+    /// Approximate minimum degree column ordering.
+    Colamd,
+    /// `perm[j]` is the position of column `j` of `A` in `A * Pc`.
+    Custom(Vec<usize>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IterativeRefinement {
+    NoRefinement,
+    Single,
+    Double,
+    Extra,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transpose {
+    NoTranspose,
+    Transpose,
+    ConjugateTranspose,
+}
+
+/// Modified ILU variant, which adds dropped entries to the diagonal to keep
+/// row sums intact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Milu {
+    /// Plain ILU, dropped entries are discarded.
+    Silu,
+    /// Adds the sum of the dropped entries of a column to its diagonal.
+    Smilu1,
+    /// Adds the sum of the absolute values of the dropped entries.
+    Smilu2,
+    /// Adds the sum of the absolute values, preserving the diagonal sign.
+    Smilu3,
+}
+
+/// Dropping rules of the incomplete factorization, combined with `|`.
+///
+/// At most one of [`DropRule::PROWS`], [`DropRule::COLUMN`] and
+/// [`DropRule::AREA`] may be set. The default is `BASIC | AREA`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DropRule(c_int);
+
+impl DropRule {
+    /// Drops entries below the drop tolerance, ILU(tau).
+    pub const BASIC: Self = Self(0x0001);
+    /// Keeps the largest `fill_factor * nnz(A) / n` rows per supernode.
+    pub const PROWS: Self = Self(0x0002);
+    /// Keeps the largest `fill_factor * nnz(A(:, j))` entries of column `j`.
+    pub const COLUMN: Self = Self(0x0004);
+    /// Limits the fill in the columns so far to `fill_factor` times the
+    /// entries of `A` in them.
+    pub const AREA: Self = Self(0x0008);
+    /// Adapts the drop tolerance during the factorization.
+    pub const DYNAMIC: Self = Self(0x0010);
+    /// Computes the secondary threshold by interpolation.
+    pub const INTERP: Self = Self(0x0100);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for DropRule {
+    fn default() -> Self {
+        DropRule::BASIC | DropRule::AREA
+    }
+}
+
+impl BitOr for DropRule {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionsError {
+    /// The diagonal pivot threshold must lie in `[0, 1]`.
+    InvalidPivotThreshold(f64),
+    /// A custom column permutation does not contain every index exactly once.
+    InvalidPermutation,
+    /// The ILU drop tolerance must lie in `[0, 1]`.
+    InvalidDropTolerance(f64),
+    /// The ILU fill factor must be at least one.
+    InvalidFillFactor(f64),
+    /// More than one of `PROWS`, `COLUMN` and `AREA` was requested.
+    InvalidDropRule,
+}
+
+/// Typed builder for [`Options`]. The defaults match `set_default_options`.
+#[derive(Clone, Debug)]
+pub struct OptionsBuilder {
+    column_permutation: ColumnPermutation,
+    equilibrate: bool,
+    iterative_refinement: IterativeRefinement,
+    transpose: Transpose,
+    diagonal_pivot_threshold: f64,
+    symmetric_mode: bool,
+    drop_tolerance: f64,
+    fill_factor: f64,
+    milu: Milu,
+    drop_rule: DropRule,
+}
+
+impl Default for OptionsBuilder {
+    fn default() -> Self {
+        Self {
+            column_permutation: ColumnPermutation::Colamd,
+            equilibrate: true,
+            iterative_refinement: IterativeRefinement::NoRefinement,
+            transpose: Transpose::NoTranspose,
+            diagonal_pivot_threshold: 1.0,
+            symmetric_mode: false,
+            drop_tolerance: 1e-4,
+            fill_factor: 10.0,
+            milu: Milu::Silu,
+            drop_rule: DropRule::default(),
+        }
+    }
+}
+
+impl OptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn column_permutation(mut self, column_permutation: ColumnPermutation) -> Self {
+        self.column_permutation = column_permutation;
+        self
+    }
+
+    /// Scales the rows and columns of `A` before factoring it. Only
+    /// [`crate::solve_expert`] does this, the other solvers factor `A` as it
+    /// is.
+    pub fn equilibrate(mut self, equilibrate: bool) -> Self {
+        self.equilibrate = equilibrate;
+        self
+    }
+
+    /// Refines the solution iteratively. Only [`crate::solve_expert`] does
+    /// this.
+    pub fn iterative_refinement(mut self, iterative_refinement: IterativeRefinement) -> Self {
+        self.iterative_refinement = iterative_refinement;
+        self
+    }
+
+    /// Solves `A^T x = b` or `A^H x = b` instead of `A x = b` in the
+    /// one-shot solves and the expert driver.
+    pub fn transpose(mut self, transpose: Transpose) -> Self {
+        self.transpose = transpose;
+        self
+    }
+
+    /// Threshold `u` in `[0, 1]` for partial pivoting: the diagonal entry is
+    /// kept as pivot if it is at least `u` times the largest candidate.
+    pub fn diagonal_pivot_threshold(mut self, threshold: f64) -> Self {
+        self.diagonal_pivot_threshold = threshold;
+        self
+    }
+
+    /// Prefers diagonal pivots, intended for diagonally dominant matrices.
+    /// Usually combined with a small pivot threshold and `MmdAtPlusA`.
+    pub fn symmetric_mode(mut self, symmetric_mode: bool) -> Self {
+        self.symmetric_mode = symmetric_mode;
+        self
+    }
+
+    /// ILU: entries below this tolerance, relative to their column, are
+    /// dropped.
+    pub fn drop_tolerance(mut self, drop_tolerance: f64) -> Self {
+        self.drop_tolerance = drop_tolerance;
+        self
+    }
+
+    /// ILU: bound on the fill, relative to the entries of `A`, used by the
+    /// secondary dropping rules.
+    pub fn fill_factor(mut self, fill_factor: f64) -> Self {
+        self.fill_factor = fill_factor;
+        self
+    }
+
+    pub fn milu(mut self, milu: Milu) -> Self {
+        self.milu = milu;
+        self
+    }
+
+    pub fn drop_rule(mut self, drop_rule: DropRule) -> Self {
+        self.drop_rule = drop_rule;
+        self
+    }
+
+    pub fn build(self) -> Result<Options, OptionsError> {
+        if !(0.0..=1.0).contains(&self.diagonal_pivot_threshold) {
+            return Err(OptionsError::InvalidPivotThreshold(
+                self.diagonal_pivot_threshold,
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.drop_tolerance) {
+            return Err(OptionsError::InvalidDropTolerance(self.drop_tolerance));
+        }
+        if !(1.0..).contains(&self.fill_factor) {
+            return Err(OptionsError::InvalidFillFactor(self.fill_factor));
+        }
+        let secondary = [DropRule::PROWS, DropRule::COLUMN, DropRule::AREA];
+        if secondary
+            .iter()
+            .filter(|&&rule| self.drop_rule.contains(rule))
+            .count()
+            > 1
+        {
+            return Err(OptionsError::InvalidDropRule);
+        }
+
+        let mut options = Options::default();
+        options.ffi.ColPerm = match self.column_permutation {
+            ColumnPermutation::Natural => ffi::colperm_t::NATURAL,
+            ColumnPermutation::MmdAtA => ffi::colperm_t::MMD_ATA,
+            ColumnPermutation::MmdAtPlusA => ffi::colperm_t::MMD_AT_PLUS_A,
+            ColumnPermutation::Colamd => ffi::colperm_t::COLAMD,
+            ColumnPermutation::Custom(perm) => {
+                let perm: Vec<c_int> = perm
+                    .into_iter()
+                    .map(|j| c_int::try_from(j).unwrap_or(-1))
+                    .collect();
+                if !is_permutation(&perm) {
+                    return Err(OptionsError::InvalidPermutation);
+                }
+                options.perm_c = Some(perm);
+                ffi::colperm_t::MY_PERMC
+            }
+        };
+        options.ffi.Equil = yes_no(self.equilibrate);
+        options.ffi.IterRefine = match self.iterative_refinement {
+            IterativeRefinement::NoRefinement => ffi::IterRefine_t::NOREFINE,
+            IterativeRefinement::Single => ffi::IterRefine_t::SLU_SINGLE,
+            IterativeRefinement::Double => ffi::IterRefine_t::SLU_DOUBLE,
+            IterativeRefinement::Extra => ffi::IterRefine_t::SLU_EXTRA,
+        };
+        options.ffi.Trans = match self.transpose {
+            Transpose::NoTranspose => ffi::trans_t::NOTRANS,
+            Transpose::Transpose => ffi::trans_t::TRANS,
+            Transpose::ConjugateTranspose => ffi::trans_t::CONJ,
+        };
+        options.ffi.DiagPivotThresh = self.diagonal_pivot_threshold;
+        options.ffi.SymmetricMode = yes_no(self.symmetric_mode);
+        options.ffi.ILU_DropTol = self.drop_tolerance;
+        options.ffi.ILU_FillFactor = self.fill_factor;
+        options.ffi.ILU_MILU = match self.milu {
+            Milu::Silu => ffi::milu_t::SILU,
+            Milu::Smilu1 => ffi::milu_t::SMILU_1,
+            Milu::Smilu2 => ffi::milu_t::SMILU_2,
+            Milu::Smilu3 => ffi::milu_t::SMILU_3,
+        };
+        options.ffi.ILU_DropRule = self.drop_rule.0;
+        Ok(options)
+    }
+}
+
+/// Whether `perm` contains every index below its length exactly once.
+fn is_permutation(perm: &[c_int]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    for &j in perm {
+        match usize::try_from(j) {
+            Ok(j) if j < perm.len() && !seen[j] => seen[j] = true,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn yes_no(value: bool) -> ffi::yes_no_t {
+    if value {
+        ffi::yes_no_t::YES
+    } else {
+        ffi::yes_no_t::NO
+    }
+}
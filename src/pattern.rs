@@ -0,0 +1,106 @@
+//! Structural checks run before a matrix reaches SuperLU.
+//!
+//! SuperLU assumes a structurally nonsingular matrix. For one without a
+//! perfect matching between rows and columns, `*pivotL` reads past the
+//! candidate rows of a column and writes through the garbage row index it
+//! finds there, instead of reporting a zero pivot.
+
+use sprs::{CsMatViewI, SpIndex};
+
+/// Finds an outer index (a column for CSC input) that a maximum bipartite
+/// matching between rows and columns leaves unmatched, or `None` if the
+/// matrix has a perfect matching.
+pub(crate) fn structurally_singular_column<N, I: SpIndex, Iptr: SpIndex>(
+    mat: CsMatViewI<N, I, Iptr>,
+) -> Option<usize> {
+    let n_outer = mat.outer_dims();
+    let n_inner = mat.inner_dims();
+    if n_outer > n_inner {
+        return Some(n_inner);
+    }
+    let indptr = mat.indptr();
+    let indices = mat.indices();
+    // Most matrices that are solved have a zero-free diagonal, which is a
+    // perfect matching already. This costs a pass over the indices and
+    // saves the search.
+    if n_outer == n_inner
+        && (0..n_outer).all(|j| {
+            indices[indptr.outer_inds_sz(j)]
+                .iter()
+                .any(|i| i.index() == j)
+        })
+    {
+        return None;
+    }
+
+    const UNMATCHED: usize = usize::MAX;
+    // outer index matched to each inner index
+    let mut matched_outer = vec![UNMATCHED; n_inner];
+    // next candidate for the cheap assignment of each outer index
+    let mut cheap: Vec<usize> = (0..n_outer)
+        .map(|j| indptr.outer_inds_sz(j).start)
+        .collect();
+    // last search that visited each outer index
+    let mut visited = vec![UNMATCHED; n_outer];
+    // Depth-first search stacks: outer index, inner index and resume
+    // position. Augmenting paths can be as long as `n_outer`, so the search
+    // keeps its own stacks rather than recursing.
+    let mut outer_stack = vec![0; n_outer];
+    let mut inner_stack = vec![0; n_outer];
+    let mut pos_stack = vec![0; n_outer];
+
+    for k in 0..n_outer {
+        let mut found = false;
+        let mut head = 0;
+        outer_stack[0] = k;
+        loop {
+            let j = outer_stack[head];
+            let range = indptr.outer_inds_sz(j);
+            if visited[j] != k {
+                visited[j] = k;
+                while cheap[j] < range.end {
+                    let i = indices[cheap[j]].index();
+                    cheap[j] += 1;
+                    if matched_outer[i] == UNMATCHED {
+                        inner_stack[head] = i;
+                        found = true;
+                        break;
+                    }
+                }
+                if found {
+                    break;
+                }
+                pos_stack[head] = range.start;
+            }
+
+            let mut descended = false;
+            while pos_stack[head] < range.end {
+                let i = indices[pos_stack[head]].index();
+                pos_stack[head] += 1;
+                let next = matched_outer[i];
+                if visited[next] == k {
+                    continue;
+                }
+                inner_stack[head] = i;
+                head += 1;
+                outer_stack[head] = next;
+                descended = true;
+                break;
+            }
+            if !descended {
+                if head == 0 {
+                    break;
+                }
+                head -= 1;
+            }
+        }
+
+        if !found {
+            return Some(k);
+        }
+        for level in 0..=head {
+            matched_outer[inner_stack[level]] = outer_stack[level];
+        }
+    }
+    None
+}
@@ -0,0 +1,61 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+thread_local! {
+    static ON_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `job` on the blocking pool, a fixed set of worker threads, one per
+/// core, that is started on first use and shared by the whole process.
+///
+/// Jobs queue up when all workers are busy, so a burst of solves does not
+/// start a thread each.
+pub(crate) fn spawn(job: impl FnOnce() + Send + 'static) {
+    static QUEUE: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+    let queue = QUEUE.get_or_init(|| Mutex::new(start_workers()));
+    // The workers never exit, so the queue stays open.
+    let _ = queue.lock().unwrap().send(Box::new(job));
+}
+
+fn start_workers() -> Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for index in 0..worker_count() {
+        let receiver = Arc::clone(&receiver);
+        thread::Builder::new()
+            .name(format!("superlu-worker-{}", index))
+            .spawn(move || work(&receiver))
+            .expect("failed to start a SuperLU worker thread");
+    }
+    sender
+}
+
+/// Number of worker threads in the pool.
+pub(crate) fn worker_count() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Whether the calling thread is one of the pool's workers. A job that waited
+/// for other jobs could deadlock once every worker does the same.
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn on_worker() -> bool {
+    ON_WORKER.with(Cell::get)
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    ON_WORKER.with(|on_worker| on_worker.set(true));
+    loop {
+        // The lock is released before the job runs.
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        // A panicking job must not take the worker down with it.
+        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{
+    check_system, columns_from_dense_data, gssv_in_place, transposed, vec_of_array1_to_array2,
+    Options, SolverError, SuperLuScalar, SuperMatrix,
+};
+use libc::{c_int, c_void, pid_t};
+use ndarray::Array1;
+use sprs::CsMat;
+use std::io;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// How often the parent checks whether the child died while waiting for data.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[cfg(test)]
+thread_local! {
+    /// Pid of the last child forked by this thread, to check that it is reaped.
+    pub(crate) static LAST_CHILD: std::cell::Cell<Option<pid_t>> = const { std::cell::Cell::new(None) };
+}
+
+/// Solves `A x = b` in a forked child process, which is killed on timeout.
+///
+/// Unlike [`crate::solve_super_lu`], a timeout stops the factorization and
+/// releases its memory right away. The solution is sent back through a pipe.
+/// If SuperLU aborts the process, e.g. on a failed allocation, this returns
+/// [`SolverError::Aborted`] instead of taking the caller down.
+///
+/// The caller may have other threads running while it forks. The child only
+/// has the forking thread, so besides SuperLU it runs nothing but
+/// async-signal-safe calls on data prepared before the fork, and exits
+/// without running destructors. SuperLU allocates with `malloc`, which relies
+/// on the C library making the allocator usable after `fork`, as glibc and
+/// the BSD libcs do.
+pub fn solve_super_lu_in_process<T: SuperLuScalar>(
+    a: CsMat<T>,
+    b: &Vec<Array1<T>>,
+    timeout: Option<Duration>,
+    options: &mut Options,
+) -> Result<Vec<Array1<T>>, SolverError> {
+    check_system(a.view(), b)?;
+    let n = a.cols();
+    let perm_c_init = options.initial_perm_c(n)?;
+
+    let mut a_mat = SuperMatrix::from_cs_mat(transposed(a, options.ffi.Trans));
+    let mut b_mat = SuperMatrix::from_ndarray(vec_of_array1_to_array2(b));
+    let mut ffi_options = options.ffi;
+    let mut data = vec![T::default(); n * b.len()];
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    let [read_fd, write_fd] = cloexec_pipe().ok_or(SolverError::Aborted)?;
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+        return Err(SolverError::Aborted);
+    }
+    if pid == 0 {
+        // The child must never return into the caller's code, not even by
+        // unwinding.
+        let sent = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            libc::close(read_fd);
+            let info = gssv_in_place::<T>(&mut a_mat, &mut b_mat, &perm_c_init, &mut ffi_options);
+            let values = match &b_mat {
+                SuperMatrix::Dense(dense) => dense.values::<T>().unwrap_or(&[]),
+                _ => &[],
+            };
+            write_all(write_fd, &info.to_ne_bytes())
+                && (info != 0 || write_all(write_fd, as_bytes(values)))
+        }));
+        unsafe { libc::_exit(if matches!(sent, Ok(true)) { 0 } else { 1 }) }
+    }
+
+    unsafe { libc::close(write_fd) };
+    #[cfg(test)]
+    LAST_CHILD.with(|child| child.set(Some(pid)));
+    // Dropping the child kills and reaps it, whatever happens below.
+    let mut child = Child {
+        pid,
+        read_fd,
+        exited: false,
+    };
+
+    // Other children forked meanwhile may hold the write end as well, so the
+    // end of the result is known from its length rather than from EOF.
+    let mut info = [0u8; mem::size_of::<c_int>()];
+    child.read_exact(&mut info, deadline)?;
+    let info = c_int::from_ne_bytes(info);
+    if info != 0 {
+        return Err(SolverError::from_info(info, n));
+    }
+    child.read_exact(as_bytes_mut(&mut data), deadline)?;
+    Ok(columns_from_dense_data(data, n))
+}
+
+struct Child {
+    pid: pid_t,
+    read_fd: c_int,
+    exited: bool,
+}
+
+impl Child {
+    /// Fills `bytes` from the pipe. Fails with `Timeout` once the deadline
+    /// passes, and with `Aborted` if the child exits without sending them.
+    fn read_exact(
+        &mut self,
+        mut bytes: &mut [u8],
+        deadline: Option<Instant>,
+    ) -> Result<(), SolverError> {
+        while !bytes.is_empty() {
+            let mut wait = POLL_INTERVAL;
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(SolverError::Timeout);
+                }
+                wait = wait.min(left);
+            }
+            if self.exited {
+                // Everything the child wrote is already in the pipe.
+                wait = Duration::ZERO;
+            }
+
+            let mut poll_fd = libc::pollfd {
+                fd: self.read_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // Rounded up, so the deadline is not polled for in a busy loop.
+            let wait_ms = wait.as_nanos().div_ceil(1_000_000) as c_int;
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, wait_ms) };
+            if ready < 0 {
+                if interrupted() {
+                    continue;
+                }
+                return Err(SolverError::Aborted);
+            }
+            if ready == 0 {
+                if self.exited {
+                    return Err(SolverError::Aborted);
+                }
+                self.check_exited();
+                continue;
+            }
+
+            let count =
+                unsafe { libc::read(self.read_fd, bytes.as_mut_ptr() as *mut c_void, bytes.len()) };
+            if count > 0 {
+                bytes = &mut bytes[count as usize..];
+            } else if count == 0 || !interrupted() {
+                return Err(SolverError::Aborted);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_exited(&mut self) {
+        let mut status = 0;
+        if unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) } == self.pid {
+            self.exited = true;
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read_fd);
+            if !self.exited {
+                // The child is done or no longer needed, either way it must
+                // not keep running.
+                libc::kill(self.pid, libc::SIGKILL);
+                let mut status = 0;
+                while libc::waitpid(self.pid, &mut status, 0) < 0 && interrupted() {}
+            }
+        }
+    }
+}
+
+/// A pipe that is not inherited by programs that other threads `exec`.
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn cloexec_pipe() -> Option<[c_int; 2]> {
+    let mut fds: [c_int; 2] = [0; 2];
+    match unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } {
+        0 => Some(fds),
+        _ => None,
+    }
+}
+
+/// A pipe that is not inherited by programs that other threads `exec`.
+/// Without `pipe2`, another thread may still exec between the two calls.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn cloexec_pipe() -> Option<[c_int; 2]> {
+    let mut fds: [c_int; 2] = [0; 2];
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return None;
+        }
+        for fd in fds {
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    Some(fds)
+}
+
+fn write_all(fd: c_int, mut bytes: &[u8]) -> bool {
+    while !bytes.is_empty() {
+        let count = unsafe { libc::write(fd, bytes.as_ptr() as *const c_void, bytes.len()) };
+        if count > 0 {
+            bytes = &bytes[count as usize..];
+        } else if count == 0 || !interrupted() {
+            return false;
+        }
+    }
+    true
+}
+
+fn interrupted() -> bool {
+    io::Error::last_os_error().kind() == io::ErrorKind::Interrupted
+}
+
+/// The scalar types are plain old data, so their bytes can be sent as they
+/// are.
+fn as_bytes<T: SuperLuScalar>(values: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values)) }
+}
+
+fn as_bytes_mut<T: SuperLuScalar>(values: &mut [T]) -> &mut [u8] {
+    unsafe {
+        std::slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, mem::size_of_val(values))
+    }
+}
@@ -0,0 +1,466 @@
+//! Scalar types SuperLU can work with and their precision-specific routines.
+#![allow(clippy::too_many_arguments)]
+
+use crate::bindings;
+use bindings::{mem_usage_t, GlobalLU_t};
+use libc::{c_char, c_int, c_void};
+use num_complex::{Complex32, Complex64};
+use num_traits::{Float, Num};
+use std::fmt::Debug;
+use superlu_sys as ffi;
+use superlu_sys::{superlu_options_t, trans_t, Dtype_t, Mtype_t, Stype_t, SuperLUStat_t};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Element type of a SuperLU matrix, dispatching to the `s*`, `d*`, `c*` or
+/// `z*` routines.
+///
+/// The routines mirror their C counterparts argument for argument. The trait
+/// is sealed, it is implemented for `f32`, `f64`, `Complex32` and `Complex64`.
+pub trait SuperLuScalar:
+    private::Sealed + Num + Copy + Default + Debug + Send + Sync + 'static
+{
+    /// Real type of scale factors, condition estimates and error bounds.
+    type Real: Float + Debug + Send + Sync + From<f32> + 'static;
+
+    const DTYPE: Dtype_t;
+
+    /// Absolute value, the modulus for complex numbers.
+    fn modulus(self) -> Self::Real;
+
+    fn from_real(real: Self::Real) -> Self;
+
+    /// Complex conjugate, the identity for real numbers.
+    fn conj(self) -> Self;
+
+    /// # Safety
+    ///
+    /// The returned buffer must be released with `SUPERLU_FREE`.
+    unsafe fn malloc(n: c_int) -> *mut Self;
+
+    /// # Safety
+    ///
+    /// The buffers must hold `nnz` values and row indices and `n + 1` column
+    /// pointers, and outlive `a`.
+    unsafe fn create_comp_col_matrix(
+        a: *mut ffi::SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut Self,
+        rowind: *mut c_int,
+        colptr: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// The buffers must hold `nnz` values and column indices and `m + 1` row
+    /// pointers, and outlive `a`.
+    unsafe fn create_comp_row_matrix(
+        a: *mut ffi::SuperMatrix,
+        m: c_int,
+        n: c_int,
+        nnz: c_int,
+        nzval: *mut Self,
+        colind: *mut c_int,
+        rowptr: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// `x` must hold `ldx * n` values and outlive `a`.
+    unsafe fn create_dense_matrix(
+        a: *mut ffi::SuperMatrix,
+        m: c_int,
+        n: c_int,
+        x: *mut Self,
+        ldx: c_int,
+    );
+
+    /// # Safety
+    ///
+    /// Same requirements as the C `?gssv`.
+    unsafe fn gssv(
+        options: *mut superlu_options_t,
+        a: *mut ffi::SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        l: *mut ffi::SuperMatrix,
+        u: *mut ffi::SuperMatrix,
+        b: *mut ffi::SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// Same requirements as the C `?gstrf`.
+    unsafe fn gstrf(
+        options: *mut superlu_options_t,
+        a: *mut ffi::SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        l: *mut ffi::SuperMatrix,
+        u: *mut ffi::SuperMatrix,
+        glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// Same requirements as the C `?gsitrf`.
+    unsafe fn gsitrf(
+        options: *mut superlu_options_t,
+        a: *mut ffi::SuperMatrix,
+        relax: c_int,
+        panel_size: c_int,
+        etree: *mut c_int,
+        work: *mut c_void,
+        lwork: c_int,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        l: *mut ffi::SuperMatrix,
+        u: *mut ffi::SuperMatrix,
+        glu: *mut GlobalLU_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// Same requirements as the C `?gstrs`.
+    unsafe fn gstrs(
+        trans: trans_t,
+        l: *mut ffi::SuperMatrix,
+        u: *mut ffi::SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        b: *mut ffi::SuperMatrix,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+
+    /// # Safety
+    ///
+    /// Same requirements as the C `?gssvx`.
+    unsafe fn gssvx(
+        options: *mut superlu_options_t,
+        a: *mut ffi::SuperMatrix,
+        perm_c: *mut c_int,
+        perm_r: *mut c_int,
+        etree: *mut c_int,
+        equed: *mut c_char,
+        r: *mut Self::Real,
+        c: *mut Self::Real,
+        l: *mut ffi::SuperMatrix,
+        u: *mut ffi::SuperMatrix,
+        work: *mut c_void,
+        lwork: c_int,
+        b: *mut ffi::SuperMatrix,
+        x: *mut ffi::SuperMatrix,
+        recip_pivot_growth: *mut Self::Real,
+        rcond: *mut Self::Real,
+        ferr: *mut Self::Real,
+        berr: *mut Self::Real,
+        glu: *mut GlobalLU_t,
+        mem_usage: *mut mem_usage_t,
+        stat: *mut SuperLUStat_t,
+        info: *mut c_int,
+    );
+}
+
+macro_rules! impl_super_lu_scalar {
+    ($t:ty, $real:ty, $dtype:ident, $modulus:ident, $from_real:expr, $conj:expr,
+     $malloc:path, $create_comp_col:path, $create_comp_row:path, $create_dense:path,
+     $gssv:path, $gstrf:path, $gsitrf:path, $gstrs:path, $gssvx:path) => {
+        impl private::Sealed for $t {}
+
+        impl SuperLuScalar for $t {
+            type Real = $real;
+
+            const DTYPE: Dtype_t = Dtype_t::$dtype;
+
+            fn modulus(self) -> $real {
+                self.$modulus()
+            }
+
+            fn from_real(real: $real) -> Self {
+                $from_real(real)
+            }
+
+            fn conj(self) -> Self {
+                $conj(self)
+            }
+
+            unsafe fn malloc(n: c_int) -> *mut Self {
+                $malloc(n) as *mut Self
+            }
+
+            unsafe fn create_comp_col_matrix(
+                a: *mut ffi::SuperMatrix,
+                m: c_int,
+                n: c_int,
+                nnz: c_int,
+                nzval: *mut Self,
+                rowind: *mut c_int,
+                colptr: *mut c_int,
+            ) {
+                $create_comp_col(
+                    a,
+                    m,
+                    n,
+                    nnz,
+                    nzval as *mut _,
+                    rowind,
+                    colptr,
+                    Stype_t::SLU_NC,
+                    Self::DTYPE,
+                    Mtype_t::SLU_GE,
+                );
+            }
+
+            unsafe fn create_comp_row_matrix(
+                a: *mut ffi::SuperMatrix,
+                m: c_int,
+                n: c_int,
+                nnz: c_int,
+                nzval: *mut Self,
+                colind: *mut c_int,
+                rowptr: *mut c_int,
+            ) {
+                $create_comp_row(
+                    a,
+                    m,
+                    n,
+                    nnz,
+                    nzval as *mut _,
+                    colind,
+                    rowptr,
+                    Stype_t::SLU_NR,
+                    Self::DTYPE,
+                    Mtype_t::SLU_GE,
+                );
+            }
+
+            unsafe fn create_dense_matrix(
+                a: *mut ffi::SuperMatrix,
+                m: c_int,
+                n: c_int,
+                x: *mut Self,
+                ldx: c_int,
+            ) {
+                $create_dense(
+                    a,
+                    m,
+                    n,
+                    x as *mut _,
+                    ldx,
+                    Stype_t::SLU_DN,
+                    Self::DTYPE,
+                    Mtype_t::SLU_GE,
+                );
+            }
+
+            unsafe fn gssv(
+                options: *mut superlu_options_t,
+                a: *mut ffi::SuperMatrix,
+                perm_c: *mut c_int,
+                perm_r: *mut c_int,
+                l: *mut ffi::SuperMatrix,
+                u: *mut ffi::SuperMatrix,
+                b: *mut ffi::SuperMatrix,
+                stat: *mut SuperLUStat_t,
+                info: *mut c_int,
+            ) {
+                $gssv(options, a, perm_c, perm_r, l, u, b, stat, info);
+            }
+
+            unsafe fn gstrf(
+                options: *mut superlu_options_t,
+                a: *mut ffi::SuperMatrix,
+                relax: c_int,
+                panel_size: c_int,
+                etree: *mut c_int,
+                work: *mut c_void,
+                lwork: c_int,
+                perm_c: *mut c_int,
+                perm_r: *mut c_int,
+                l: *mut ffi::SuperMatrix,
+                u: *mut ffi::SuperMatrix,
+                glu: *mut GlobalLU_t,
+                stat: *mut SuperLUStat_t,
+                info: *mut c_int,
+            ) {
+                $gstrf(
+                    options, a, relax, panel_size, etree, work, lwork, perm_c, perm_r, l, u, glu,
+                    stat, info,
+                );
+            }
+
+            unsafe fn gsitrf(
+                options: *mut superlu_options_t,
+                a: *mut ffi::SuperMatrix,
+                relax: c_int,
+                panel_size: c_int,
+                etree: *mut c_int,
+                work: *mut c_void,
+                lwork: c_int,
+                perm_c: *mut c_int,
+                perm_r: *mut c_int,
+                l: *mut ffi::SuperMatrix,
+                u: *mut ffi::SuperMatrix,
+                glu: *mut GlobalLU_t,
+                stat: *mut SuperLUStat_t,
+                info: *mut c_int,
+            ) {
+                $gsitrf(
+                    options, a, relax, panel_size, etree, work, lwork, perm_c, perm_r, l, u, glu,
+                    stat, info,
+                );
+            }
+
+            unsafe fn gstrs(
+                trans: trans_t,
+                l: *mut ffi::SuperMatrix,
+                u: *mut ffi::SuperMatrix,
+                perm_c: *mut c_int,
+                perm_r: *mut c_int,
+                b: *mut ffi::SuperMatrix,
+                stat: *mut SuperLUStat_t,
+                info: *mut c_int,
+            ) {
+                $gstrs(trans, l, u, perm_c, perm_r, b, stat, info);
+            }
+
+            unsafe fn gssvx(
+                options: *mut superlu_options_t,
+                a: *mut ffi::SuperMatrix,
+                perm_c: *mut c_int,
+                perm_r: *mut c_int,
+                etree: *mut c_int,
+                equed: *mut c_char,
+                r: *mut Self::Real,
+                c: *mut Self::Real,
+                l: *mut ffi::SuperMatrix,
+                u: *mut ffi::SuperMatrix,
+                work: *mut c_void,
+                lwork: c_int,
+                b: *mut ffi::SuperMatrix,
+                x: *mut ffi::SuperMatrix,
+                recip_pivot_growth: *mut Self::Real,
+                rcond: *mut Self::Real,
+                ferr: *mut Self::Real,
+                berr: *mut Self::Real,
+                glu: *mut GlobalLU_t,
+                mem_usage: *mut mem_usage_t,
+                stat: *mut SuperLUStat_t,
+                info: *mut c_int,
+            ) {
+                $gssvx(
+                    options,
+                    a,
+                    perm_c,
+                    perm_r,
+                    etree,
+                    equed,
+                    r,
+                    c,
+                    l,
+                    u,
+                    work,
+                    lwork,
+                    b,
+                    x,
+                    recip_pivot_growth,
+                    rcond,
+                    ferr,
+                    berr,
+                    glu,
+                    mem_usage,
+                    stat,
+                    info,
+                );
+            }
+        }
+    };
+}
+
+impl_super_lu_scalar!(
+    f32,
+    f32,
+    SLU_S,
+    abs,
+    f32::from,
+    std::convert::identity,
+    bindings::floatMalloc,
+    bindings::sCreate_CompCol_Matrix,
+    bindings::sCreate_CompRow_Matrix,
+    bindings::sCreate_Dense_Matrix,
+    bindings::sgssv,
+    bindings::sgstrf,
+    bindings::sgsitrf,
+    bindings::sgstrs,
+    bindings::sgssvx
+);
+
+impl_super_lu_scalar!(
+    f64,
+    f64,
+    SLU_D,
+    abs,
+    f64::from,
+    std::convert::identity,
+    ffi::doubleMalloc,
+    ffi::dCreate_CompCol_Matrix,
+    bindings::dCreate_CompRow_Matrix,
+    ffi::dCreate_Dense_Matrix,
+    ffi::dgssv,
+    bindings::dgstrf,
+    bindings::dgsitrf,
+    ffi::dgstrs,
+    bindings::dgssvx
+);
+
+impl_super_lu_scalar!(
+    Complex32,
+    f32,
+    SLU_C,
+    norm,
+    Complex32::from,
+    |z: Complex32| Complex32::conj(&z),
+    bindings::complexMalloc,
+    bindings::cCreate_CompCol_Matrix,
+    bindings::cCreate_CompRow_Matrix,
+    bindings::cCreate_Dense_Matrix,
+    bindings::cgssv,
+    bindings::cgstrf,
+    bindings::cgsitrf,
+    bindings::cgstrs,
+    bindings::cgssvx
+);
+
+impl_super_lu_scalar!(
+    Complex64,
+    f64,
+    SLU_Z,
+    norm,
+    Complex64::from,
+    |z: Complex64| Complex64::conj(&z),
+    bindings::doublecomplexMalloc,
+    bindings::zCreate_CompCol_Matrix,
+    bindings::zCreate_CompRow_Matrix,
+    bindings::zCreate_Dense_Matrix,
+    bindings::zgssv,
+    bindings::zgstrf,
+    bindings::zgsitrf,
+    bindings::zgstrs,
+    bindings::zgssvx
+);
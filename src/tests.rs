@@ -1,10 +1,24 @@
 #[cfg(test)]
+#[allow(
+    clippy::module_inception,
+    clippy::useless_conversion,
+    clippy::useless_vec
+)]
 mod tests {
     use crate::SolverError;
-    use crate::SuperMatrix;
-    use crate::{solve_super_lu, Options};
+    use crate::{
+        bicgstab, gmres, solve_batch, solve_expert, solve_super_lu, solve_super_lu_array,
+        solve_super_lu_async, solve_super_lu_in_place, solve_super_lu_in_process,
+        solve_super_lu_vector, solve_super_lu_view, ColumnPermutation, ConvergenceReason, DropRule,
+        Equilibration, IncompleteLu, IterativeRefinement, KrylovOptions, KrylovReport,
+        LuFactorization, Milu, Options, OptionsBuilder, OptionsError, Transpose,
+    };
+    use crate::{DenseMatrix, SuperMatrix};
     use ndarray::{arr1, arr2, Array1, Array2};
-    use sprs::{CsMat, TriMat};
+    use num_complex::{Complex32, Complex64};
+    use sprs::{CsMat, CsMatI, CsVec, TriMat};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
     use std::mem;
     use std::time::Duration;
     use superlu_sys::{Dtype_t, Mtype_t, Stype_t};
@@ -236,7 +250,6 @@ mod tests {
         }
     }
 
-
     #[test]
     fn test_solver_singular_matrix() {
         let mut tri_mat = TriMat::new((5, 5));
@@ -258,7 +271,7 @@ mod tests {
                 panic!("Singular matrix to caught");
             }
             Err(e) => match e {
-                SolverError::Unsolvable => {}
+                SolverError::Singular { .. } => {}
                 _ => {
                     panic!("Singular matrix to caught");
                 }
@@ -278,7 +291,7 @@ mod tests {
                 panic!("Singular matrix to caught");
             }
             Err(e) => match e {
-                SolverError::Unsolvable => {}
+                SolverError::Singular { .. } => {}
                 _ => {
                     panic!("Singular matrix to caught");
                 }
@@ -297,7 +310,7 @@ mod tests {
                 panic!("Dimension error to caught");
             }
             Err(e) => match e {
-                SolverError::Conflict => {}
+                SolverError::DimensionMismatch { .. } => {}
                 _ => {
                     panic!("Dimension error to caught");
                 }
@@ -316,7 +329,7 @@ mod tests {
                 panic!("Dimension error to caught");
             }
             Err(e) => match e {
-                SolverError::Conflict => {}
+                SolverError::DimensionMismatch { .. } => {}
                 _ => {
                     panic!("Dimension error to caught");
                 }
@@ -393,4 +406,1802 @@ mod tests {
         }
         assert!(!super_matrix.raw().Store.is_null());
     }
+
+    fn example_matrix() -> CsMat<f64> {
+        let values = vec![
+            19.0, 12.0, 12.0, 21.0, 12.0, 12.0, 21.0, 16.0, 21.0, 5.0, 21.0, 18.0,
+        ];
+        let row_indices = vec![0, 1, 4, 1, 2, 4, 0, 2, 0, 3, 3, 4];
+        let col_ptrs = vec![0, 3, 6, 8, 10, 12];
+        CsMat::new_csc((5, 5), col_ptrs, row_indices, values)
+    }
+
+    fn example_solution() -> Array1<f64> {
+        arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ])
+    }
+
+    #[test]
+    fn test_factorization_solve() {
+        let a_mat = example_matrix();
+        let options = Options::default();
+        let lu = LuFactorization::new(a_mat, &options).unwrap();
+
+        let expected_vec = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+
+        let first = lu.solve(&[arr1(&[1., 1., 1., 1., 1.])]).unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(array1s_close(&first[0], &expected_vec, 1e-4));
+
+        let second = lu
+            .solve(&[arr1(&[2., 2., 2., 2., 2.]), arr1(&[3., 3., 3., 3., 3.])])
+            .unwrap();
+        assert_eq!(second.len(), 2);
+        assert!(array1s_close(
+            &second[0],
+            &(expected_vec.clone() * 2.),
+            1e-4
+        ));
+        assert!(array1s_close(&second[1], &(expected_vec * 3.), 1e-4));
+    }
+
+    #[test]
+    fn test_factorization_singular_matrix() {
+        let mut tri_mat = TriMat::new((5, 5));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(1, 0, 1.0);
+        tri_mat.add_triplet(2, 2, 1.0);
+        tri_mat.add_triplet(3, 3, 1.0);
+        tri_mat.add_triplet(4, 0, 1.0);
+        tri_mat.add_triplet(4, 1, 1.0);
+        tri_mat.add_triplet(4, 2, 1.0);
+        tri_mat.add_triplet(4, 3, 1.0);
+
+        let res = LuFactorization::new(tri_mat.to_csc(), &Options::default());
+        match res {
+            Ok(_) => panic!("Singular matrix not caught"),
+            Err(SolverError::Singular { column: 4 }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_factorization_rhs_mismatch() {
+        let mut tri_mat = TriMat::new((3, 3));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 2.0);
+        tri_mat.add_triplet(2, 2, 4.0);
+        let lu = LuFactorization::new(tri_mat.to_csc(), &Options::default()).unwrap();
+        match lu.solve(&[arr1(&[1., 1.])]) {
+            Ok(_) => panic!("Dimension error not caught"),
+            Err(SolverError::DimensionMismatch {
+                expected: 3,
+                found: 2,
+            }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_factorization_structurally_singular() {
+        let mut tri_mat = TriMat::new((3, 3));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(0, 1, 2.0);
+        tri_mat.add_triplet(1, 2, 3.0);
+        tri_mat.add_triplet(2, 2, 4.0);
+
+        for _ in 0..10 {
+            let res = LuFactorization::new(tri_mat.to_csc(), &Options::default());
+            match res {
+                Ok(_) => panic!("Singular matrix not caught"),
+                Err(SolverError::Singular { column: 1 }) => {}
+                Err(e) => panic!("Unexpected error: {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_factorization_refactor() {
+        let a_mat = example_matrix();
+        let mut lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+
+        let expected_vec = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+        let rhs = [arr1(&[1., 1., 1., 1., 1.])];
+
+        lu.refactor(&a_mat.map(|x| x * 2.)).unwrap();
+        let sol = lu.solve(&rhs).unwrap();
+        assert!(array1s_close(&sol[0], &(expected_vec.clone() / 2.), 1e-4));
+
+        lu.refactor_same_row_perm(&a_mat.map(|x| x * 4.)).unwrap();
+        let sol = lu.solve(&rhs).unwrap();
+        assert!(array1s_close(&sol[0], &(expected_vec / 4.), 1e-4));
+    }
+
+    #[test]
+    fn test_factorization_refactor_pattern_mismatch() {
+        let mut tri_mat = TriMat::new((3, 3));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 2.0);
+        tri_mat.add_triplet(2, 2, 4.0);
+        let mut lu = LuFactorization::new(tri_mat.to_csc(), &Options::default()).unwrap();
+
+        tri_mat.add_triplet(2, 0, 1.0);
+        match lu.refactor(&tri_mat.to_csc()) {
+            Ok(_) => panic!("Pattern change not caught"),
+            Err(SolverError::Conflict) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let sol = lu.solve(&[arr1(&[1., 2., 4.])]).unwrap();
+        assert!(array1s_close(&sol[0], &arr1(&[1., 1., 1.]), 1e-12));
+    }
+
+    #[test]
+    fn test_factorization_refactor_singular() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(0, 1, 1.0);
+        tri_mat.add_triplet(1, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 2.0);
+        let a_mat: CsMat<f64> = tri_mat.to_csc();
+        let mut lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+
+        let singular = CsMat::new_csc(
+            (2, 2),
+            a_mat.indptr().to_proper().into_owned(),
+            a_mat.indices().to_vec(),
+            vec![1., 1., 1., 1.],
+        );
+        match lu.refactor_same_row_perm(&singular) {
+            Ok(_) => panic!("Singular matrix not caught"),
+            Err(SolverError::Singular { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        assert!(lu.solve(&[arr1(&[1., 1.])]).is_err());
+        assert!(lu.l_matrix().is_none() && lu.u_matrix().is_none());
+
+        lu.refactor_same_row_perm(&a_mat).unwrap();
+        let sol = lu.solve(&[arr1(&[2., 3.])]).unwrap();
+        assert!(array1s_close(&sol[0], &arr1(&[1., 1.]), 1e-12));
+    }
+
+    fn arrow_matrix(size: usize) -> CsMat<f64> {
+        let mut tri_mat = TriMat::new((size, size));
+        tri_mat.add_triplet(0, 0, 4.0);
+        for i in 1..size {
+            tri_mat.add_triplet(0, i, 1.0);
+            tri_mat.add_triplet(i, 0, 1.0);
+            tri_mat.add_triplet(i, i, 4.0);
+        }
+        tri_mat.to_csc()
+    }
+
+    #[test]
+    fn test_factorization_col_perm() {
+        use ffi::colperm_t::*;
+
+        let size = 6;
+        let mut options = Options::default();
+        options.ffi.ColPerm = NATURAL;
+        let lu = LuFactorization::new(arrow_matrix(size), &options).unwrap();
+        let identity: Vec<_> = (0..size as i32).collect();
+        assert_eq!(lu.perm_c(), &identity[..]);
+
+        options.ffi.ColPerm = MMD_AT_PLUS_A;
+        let lu = LuFactorization::new(arrow_matrix(size), &options).unwrap();
+        assert_ne!(lu.perm_c(), &identity[..]);
+        assert_eq!(lu.perm_c()[0], size as i32 - 1);
+    }
+
+    #[test]
+    fn test_solver_honors_options() {
+        use ffi::colperm_t::*;
+        use ffi::fact_t::*;
+
+        let b_mat = vec![arr1(&[1., 1., 1., 1.])];
+        let mut options = Options::default();
+        options.ffi.ColPerm = MY_PERMC;
+        let res = solve_super_lu(arrow_matrix(4), &b_mat, None, &mut options).unwrap();
+        let expected = arr1(&[1. / 13., 3. / 13., 3. / 13., 3. / 13.]);
+        assert!(array1s_close(&res[0], &expected, 1e-12));
+
+        // dgssv only accepts `Fact = DOFACT`, so this must not be reset.
+        options.ffi.Fact = SamePattern;
+        let res = solve_super_lu(arrow_matrix(4), &b_mat, None, &mut options);
+        match res {
+            Ok(_) => panic!("Options not passed to dgssv"),
+            Err(SolverError::InvalidArgument { position: 1 }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // The column permutation changes the rounding of the solution.
+        // dgssv runs the same arithmetic as dgstrf and dgstrs, so each
+        // ordering must reproduce the factorization with that ordering.
+        let mut tri_mat = TriMat::new((64, 64));
+        for (&value, (row, col)) in laplacian_2d(8).iter() {
+            tri_mat.add_triplet(
+                row,
+                col,
+                value * (1. + ((row + 2 * col) as f64).sqrt() / 7.),
+            );
+        }
+        let a_mat: CsMat<f64> = tri_mat.to_csc();
+        let b_mat = vec![Array1::from_shape_fn(64, |i| (i as f64).sqrt())];
+        let mut solutions = Vec::new();
+        for column_permutation in [
+            ColumnPermutation::Natural,
+            ColumnPermutation::Colamd,
+            ColumnPermutation::Custom((0..64).rev().collect()),
+        ] {
+            let mut options = Options::builder()
+                .column_permutation(column_permutation)
+                .build()
+                .unwrap();
+            let lu = LuFactorization::new(a_mat.clone(), &options).unwrap();
+            let expected = lu.solve(&b_mat).unwrap();
+            let res = solve_super_lu(a_mat.clone(), &b_mat, None, &mut options).unwrap();
+            assert_eq!(res, expected);
+            solutions.push(res);
+        }
+        assert_ne!(solutions[0], solutions[1]);
+        assert_ne!(solutions[0], solutions[2]);
+        assert_ne!(solutions[1], solutions[2]);
+    }
+
+    #[test]
+    fn test_options_builder_defaults() {
+        let built = OptionsBuilder::new().build().unwrap();
+        let default = Options::default();
+        assert!(matches!(built.ffi.ColPerm, ffi::colperm_t::COLAMD));
+        assert!(matches!(built.ffi.RowPerm, ffi::rowperm_t::NOROWPERM));
+        assert!(matches!(built.ffi.Equil, ffi::yes_no_t::YES));
+        assert!(matches!(built.ffi.IterRefine, ffi::IterRefine_t::NOREFINE));
+        assert!(matches!(built.ffi.Trans, ffi::trans_t::NOTRANS));
+        assert!(matches!(built.ffi.SymmetricMode, ffi::yes_no_t::NO));
+        assert_eq!(built.ffi.DiagPivotThresh, default.ffi.DiagPivotThresh);
+        assert!(built.perm_c.is_none());
+    }
+
+    #[test]
+    fn test_options_builder_values() {
+        let options = Options::builder()
+            .column_permutation(ColumnPermutation::MmdAtPlusA)
+            .equilibrate(false)
+            .iterative_refinement(IterativeRefinement::Double)
+            .transpose(Transpose::ConjugateTranspose)
+            .diagonal_pivot_threshold(0.1)
+            .symmetric_mode(true)
+            .build()
+            .unwrap();
+        assert!(matches!(options.ffi.ColPerm, ffi::colperm_t::MMD_AT_PLUS_A));
+        assert!(matches!(options.ffi.Equil, ffi::yes_no_t::NO));
+        assert!(matches!(
+            options.ffi.IterRefine,
+            ffi::IterRefine_t::SLU_DOUBLE
+        ));
+        assert!(matches!(options.ffi.Trans, ffi::trans_t::CONJ));
+        assert!(matches!(options.ffi.SymmetricMode, ffi::yes_no_t::YES));
+        assert_eq!(options.ffi.DiagPivotThresh, 0.1);
+    }
+
+    #[test]
+    fn test_options_builder_invalid() {
+        let res = Options::builder().diagonal_pivot_threshold(1.5).build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidPivotThreshold(1.5)));
+        let res = Options::builder()
+            .diagonal_pivot_threshold(f64::NAN)
+            .build();
+        assert!(matches!(res, Err(OptionsError::InvalidPivotThreshold(_))));
+
+        let res = Options::builder()
+            .column_permutation(ColumnPermutation::Custom(vec![0, 2, 2]))
+            .build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidPermutation));
+        let res = Options::builder()
+            .column_permutation(ColumnPermutation::Custom(vec![0, 3, 1]))
+            .build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidPermutation));
+    }
+
+    #[test]
+    fn test_options_custom_col_perm() {
+        let size = 6;
+        let perm = vec![5, 0, 1, 2, 3, 4];
+        let options = Options::builder()
+            .column_permutation(ColumnPermutation::Custom(perm))
+            .build()
+            .unwrap();
+        let lu = LuFactorization::new(arrow_matrix(size), &options).unwrap();
+        assert_eq!(lu.perm_c(), &[5, 0, 1, 2, 3, 4]);
+
+        let mut options = Options::builder()
+            .column_permutation(ColumnPermutation::Custom(vec![3, 0, 1, 2]))
+            .build()
+            .unwrap();
+        let b_mat = vec![arr1(&[1., 1., 1., 1.])];
+        let res = solve_super_lu(arrow_matrix(4), &b_mat, None, &mut options).unwrap();
+        let expected = arr1(&[1. / 13., 3. / 13., 3. / 13., 3. / 13.]);
+        assert!(array1s_close(&res[0], &expected, 1e-12));
+
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.])];
+        match solve_super_lu(arrow_matrix(5), &b_mat, None, &mut options) {
+            Ok(_) => panic!("Permutation length mismatch not caught"),
+            Err(SolverError::DimensionMismatch {
+                expected: 5,
+                found: 4,
+            }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // A hand-built permutation is checked before SuperLU indexes with it.
+        let b_mat = vec![arr1(&[1., 1., 1., 1.])];
+        for perm_c in [vec![0, 0, 1, 2], vec![0, 1, 2, 4], vec![-1, 0, 1, 2]] {
+            let mut options = Options::default();
+            options.ffi.ColPerm = ffi::colperm_t::MY_PERMC;
+            options.perm_c = Some(perm_c);
+            let res = solve_super_lu(arrow_matrix(4), &b_mat, None, &mut options);
+            assert_eq!(res, Err(SolverError::InvalidPermutation));
+            let res = solve_expert(arrow_matrix(4), &b_mat, None, &mut options);
+            assert_eq!(res.err(), Some(SolverError::InvalidPermutation));
+            let res = LuFactorization::new(arrow_matrix(4), &options);
+            assert_eq!(res.err(), Some(SolverError::InvalidPermutation));
+        }
+    }
+
+    #[test]
+    fn test_solve_expert() {
+        let a_mat = example_matrix();
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.]), arr1(&[2., 2., 2., 2., 2.])];
+        let mut options = Options::builder()
+            .iterative_refinement(IterativeRefinement::Double)
+            .build()
+            .unwrap();
+        let report =
+            solve_expert(a_mat, &b_mat, Some(Duration::from_secs(5)), &mut options).unwrap();
+
+        let expected_vec = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+        assert_eq!(report.solution.len(), 2);
+        assert!(array1s_close(&report.solution[0], &expected_vec, 1e-10));
+        assert!(array1s_close(
+            &report.solution[1],
+            &(expected_vec * 2.),
+            1e-10
+        ));
+        assert!(report.rcond > 1e-3 && report.rcond <= 1.);
+        assert!(report.recip_pivot_growth > 0.);
+        assert_eq!(report.ferr.len(), 2);
+        assert_eq!(report.berr.len(), 2);
+        for j in 0..2 {
+            assert!(report.ferr[j] < 1e-10);
+            assert!(report.berr[j] < 1e-10);
+        }
+        assert_eq!(report.row_scale.len(), 5);
+        assert_eq!(report.col_scale.len(), 5);
+    }
+
+    #[test]
+    fn test_solve_expert_ill_conditioned() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(0, 1, 1.0);
+        tri_mat.add_triplet(1, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 1.0 + 1e-12);
+        let b_mat = vec![arr1(&[2., 2. + 1e-12])];
+        let mut options = Options::default();
+        let report = solve_expert(tri_mat.to_csc(), &b_mat, None, &mut options).unwrap();
+        assert!(report.rcond < 1e-10);
+        assert!(array1s_close(&report.solution[0], &arr1(&[1., 1.]), 1e-3));
+    }
+
+    #[test]
+    fn test_solve_expert_equilibration() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, 1e10);
+        tri_mat.add_triplet(0, 1, 2e10);
+        tri_mat.add_triplet(1, 0, 3.0);
+        tri_mat.add_triplet(1, 1, 1.0);
+        let b_mat = vec![arr1(&[3e10, 4.])];
+        let mut options = Options::default();
+        let report = solve_expert(tri_mat.to_csc(), &b_mat, None, &mut options).unwrap();
+        assert_ne!(report.equed, Equilibration::None);
+        assert!(report.row_scale[0] < 1e-5);
+        assert!(array1s_close(&report.solution[0], &arr1(&[1., 1.]), 1e-10));
+
+        let mut options = Options::builder().equilibrate(false).build().unwrap();
+        let report = solve_expert(tri_mat.to_csc(), &b_mat, None, &mut options).unwrap();
+        assert_eq!(report.equed, Equilibration::None);
+        assert_eq!(report.row_scale, vec![1., 1.]);
+    }
+
+    #[test]
+    fn test_solve_expert_singular() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(0, 1, 1.0);
+        tri_mat.add_triplet(1, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 1.0);
+        let b_mat = vec![arr1(&[1., 1.])];
+        let mut options = Options::default();
+        match solve_expert(tri_mat.to_csc(), &b_mat, None, &mut options) {
+            Ok(_) => panic!("Singular matrix not caught"),
+            Err(SolverError::Singular { column: 1 }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_solver_error_from_info() {
+        assert_eq!(
+            SolverError::from_info(-3, 5),
+            SolverError::InvalidArgument { position: 3 }
+        );
+        assert_eq!(
+            SolverError::from_info(2, 5),
+            SolverError::Singular { column: 1 }
+        );
+        assert_eq!(
+            SolverError::from_info(1029, 5),
+            SolverError::OutOfMemory { bytes: 1024 }
+        );
+        let err: Box<dyn std::error::Error> = Box::new(SolverError::Singular { column: 1 });
+        assert_eq!(
+            err.to_string(),
+            "matrix is singular, zero pivot in column 1"
+        );
+    }
+
+    fn example_matrix_f32() -> CsMat<f32> {
+        example_matrix().map(|&value| value as f32)
+    }
+
+    #[test]
+    fn test_solver_f32() {
+        let b_mat: Vec<Array1<f32>> = vec![arr1(&[1., 1., 1., 1., 1.])];
+        let mut options = Options::default();
+        let res = solve_super_lu(example_matrix_f32(), &b_mat, None, &mut options).unwrap();
+        let expected: Array1<f32> = arr1(&[-0.03125, 0.06547619, 0.013392857, 0.0625, 0.0327381]);
+        assert_eq!(res.len(), 1);
+        for (x, e) in res[0].iter().zip(expected.iter()) {
+            assert!(((x - e) / e).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_factorization_f32() {
+        let lu = LuFactorization::new(example_matrix_f32(), &Options::default()).unwrap();
+        let sol = lu.solve(&[arr1(&[2f32, 2., 2., 2., 2.])]).unwrap();
+        let expected: Array1<f32> = arr1(&[-0.0625, 0.13095238, 0.026785714, 0.125, 0.06547619]);
+        for (x, e) in sol[0].iter().zip(expected.iter()) {
+            assert!(((x - e) / e).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_from_ndarray_f32() {
+        let array = Array2::from_shape_vec((2, 2), vec![1f32, 2., 3., 4.]).unwrap();
+        let super_matrix = SuperMatrix::from_ndarray(array.clone());
+        assert!(matches!(super_matrix.raw().Dtype, Dtype_t::SLU_S));
+        assert!(super_matrix.dense_values::<f64>().is_none());
+        assert_eq!(super_matrix.into_ndarray::<f32>().unwrap(), array);
+    }
+
+    fn complex_matrix() -> CsMat<Complex64> {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, Complex64::new(1., 1.));
+        tri_mat.add_triplet(0, 1, Complex64::new(2., 0.));
+        tri_mat.add_triplet(1, 1, Complex64::new(3., -1.));
+        tri_mat.to_csc()
+    }
+
+    #[test]
+    fn test_solver_complex() {
+        let b_mat = vec![arr1(&[Complex64::new(1., 3.), Complex64::new(1., 3.)])];
+        let mut options = Options::default();
+        let res = solve_super_lu(complex_matrix(), &b_mat, None, &mut options).unwrap();
+        let expected = [Complex64::new(1., 0.), Complex64::new(0., 1.)];
+        for (x, e) in res[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_factorization_complex() {
+        let lu = LuFactorization::new(complex_matrix(), &Options::default()).unwrap();
+        let sol = lu
+            .solve(&[arr1(&[Complex64::new(2., 6.), Complex64::new(2., 6.)])])
+            .unwrap();
+        let expected = [Complex64::new(2., 0.), Complex64::new(0., 2.)];
+        for (x, e) in sol[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_from_ndarray_complex() {
+        let array =
+            Array2::from_shape_vec((2, 1), vec![Complex64::new(1., 2.), Complex64::new(3., 4.)])
+                .unwrap();
+        let super_matrix = SuperMatrix::from_ndarray(array.clone());
+        assert!(matches!(super_matrix.raw().Dtype, Dtype_t::SLU_Z));
+        assert!(super_matrix.dense_values::<f64>().is_none());
+        assert_eq!(super_matrix.into_ndarray::<Complex64>().unwrap(), array);
+    }
+
+    #[test]
+    fn test_solver_complex32() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, Complex32::new(1., 1.));
+        tri_mat.add_triplet(0, 1, Complex32::new(2., 0.));
+        tri_mat.add_triplet(1, 1, Complex32::new(3., -1.));
+        let a_mat: CsMat<Complex32> = tri_mat.to_csc();
+        let b_mat = vec![arr1(&[Complex32::new(1., 3.), Complex32::new(1., 3.)])];
+        let expected = [Complex32::new(1., 0.), Complex32::new(0., 1.)];
+
+        let mut options = Options::default();
+        let res = solve_super_lu(a_mat.clone(), &b_mat, None, &mut options).unwrap();
+        for (x, e) in res[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-5);
+        }
+
+        let lu = LuFactorization::new(a_mat, &Options::default()).unwrap();
+        let sol = lu.solve(&b_mat).unwrap();
+        for (x, e) in sol[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_solve_expert_generic() {
+        let b_mat: Vec<Array1<f32>> = vec![arr1(&[1., 1., 1., 1., 1.])];
+        let mut options = Options::default();
+        let report = solve_expert(example_matrix_f32(), &b_mat, None, &mut options).unwrap();
+        let rcond: f32 = report.rcond;
+        assert!(rcond > 1e-3 && rcond <= 1.);
+        assert!(((report.solution[0][3] - 0.0625) / 0.0625).abs() < 1e-4);
+
+        let b_mat = vec![arr1(&[Complex64::new(1., 3.), Complex64::new(1., 3.)])];
+        let report = solve_expert(complex_matrix(), &b_mat, None, &mut options).unwrap();
+        let expected = [Complex64::new(1., 0.), Complex64::new(0., 1.)];
+        for (x, e) in report.solution[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-12);
+        }
+        assert!(report.rcond > 0.);
+    }
+
+    #[test]
+    fn test_solver_csr() {
+        let a_csr = example_matrix().to_csr();
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.])];
+        let expected = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+
+        let mut options = Options::default();
+        let res = solve_super_lu(a_csr.clone(), &b_mat, None, &mut options).unwrap();
+        assert!(array1s_close(&res[0], &expected, 1e-10));
+
+        let report = solve_expert(a_csr.clone(), &b_mat, None, &mut options).unwrap();
+        assert!(array1s_close(&report.solution[0], &expected, 1e-10));
+
+        let mut lu = LuFactorization::new(a_csr.clone(), &options).unwrap();
+        let sol = lu.solve(&b_mat).unwrap();
+        assert!(array1s_close(&sol[0], &expected, 1e-10));
+        lu.refactor(&a_csr.map(|x| 2. * x)).unwrap();
+        let sol = lu.solve(&b_mat).unwrap();
+        assert!(array1s_close(&sol[0], &(expected * 0.5), 1e-10));
+    }
+
+    #[test]
+    fn test_solve_expert_csr_equilibration() {
+        let mut tri_mat = TriMat::new((2, 2));
+        tri_mat.add_triplet(0, 0, 1e10);
+        tri_mat.add_triplet(0, 1, 2e10);
+        tri_mat.add_triplet(1, 0, 3.0);
+        tri_mat.add_triplet(1, 1, 1.0);
+        let b_mat = vec![arr1(&[3e10, 4.])];
+        let mut options = Options::default();
+        let report = solve_expert(tri_mat.to_csr(), &b_mat, None, &mut options).unwrap();
+        assert!(array1s_close(&report.solution[0], &arr1(&[1., 1.]), 1e-10));
+        // `?gssvx` scales the columns of the transpose, which are the rows of `A`.
+        assert_eq!(report.equed, Equilibration::Row);
+        assert!(report.row_scale[1] > 1e9 * report.row_scale[0]);
+        assert_eq!(report.col_scale, vec![1., 1.]);
+    }
+
+    #[test]
+    fn test_from_csr_mat() {
+        let mut tri_mat = TriMat::new((2, 3));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(1, 2, 2.0);
+        let csr: CsMat<f64> = tri_mat.to_csr();
+
+        let super_matrix = SuperMatrix::from_cs_mat(csr.clone());
+        assert!(matches!(super_matrix.raw().Stype, Stype_t::SLU_NR));
+        assert_eq!((super_matrix.nrows(), super_matrix.ncols()), (2, 3));
+
+        let super_matrix = SuperMatrix::from_csc_mat(csr);
+        assert!(matches!(super_matrix.raw().Stype, Stype_t::SLU_NC));
+        assert_eq!((super_matrix.nrows(), super_matrix.ncols()), (2, 3));
+    }
+
+    #[test]
+    fn test_solver_view() {
+        let a_mat: CsMatI<f64, i32> = example_matrix().to_other_types();
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.])];
+        let expected = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+
+        let mut options = Options::default();
+        let res = solve_super_lu_view(a_mat.view(), &b_mat, &mut options).unwrap();
+        assert!(array1s_close(&res[0], &expected, 1e-10));
+
+        let a_csr = a_mat.to_csr();
+        let res = solve_super_lu_view(a_csr.view(), &b_mat, &mut options).unwrap();
+        assert!(array1s_close(&res[0], &expected, 1e-10));
+
+        let a_usize: CsMat<f64> = a_mat.to_other_types();
+        let res = solve_super_lu_view(a_usize.view(), &b_mat, &mut options).unwrap();
+        assert!(array1s_close(&res[0], &expected, 1e-10));
+    }
+
+    #[test]
+    fn test_from_cs_view_borrows() {
+        let a_mat: CsMatI<f64, i32> = CsMatI::new_csc(
+            (3, 3),
+            vec![0, 2, 3, 5],
+            vec![0, 2, 1, 0, 2],
+            vec![1., 2., 3., 4., 5.],
+        );
+        let super_matrix = SuperMatrix::from_cs_view(a_mat.view());
+        unsafe {
+            let store = &*(super_matrix.raw().Store as *const ffi::NCformat);
+            assert_eq!(store.nnz, 5);
+            assert_eq!(store.nzval as *const f64, a_mat.data().as_ptr());
+            assert_eq!(store.rowind as *const i32, a_mat.indices().as_ptr());
+            assert_eq!(
+                store.colptr as *const i32,
+                a_mat.indptr().raw_storage().as_ptr()
+            );
+        }
+
+        // An outer slice only needs a rebased copy of the index pointer.
+        let slice = a_mat.slice_outer(1..3);
+        let super_matrix = SuperMatrix::from_cs_view(slice);
+        assert_eq!((super_matrix.nrows(), super_matrix.ncols()), (3, 2));
+        unsafe {
+            let store = &*(super_matrix.raw().Store as *const ffi::NCformat);
+            assert_eq!(store.nnz, 3);
+            assert_eq!(std::slice::from_raw_parts(store.colptr, 3), &[0, 1, 3]);
+            assert_eq!(std::slice::from_raw_parts(store.rowind, 3), &[1, 0, 2]);
+            assert_eq!(store.nzval as *const f64, a_mat.data()[2..].as_ptr());
+        }
+    }
+
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                let _ = ALLOCATED.try_with(|a| a.set(a.get() + layout.size() as isize));
+                let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            let _ = ALLOCATED.try_with(|a| a.set(a.get() - layout.size() as isize));
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    /// Bytes allocated and not yet freed by the current thread.
+    fn allocated_bytes() -> isize {
+        ALLOCATED.with(|a| a.get())
+    }
+
+    /// Allocations made by the current thread so far.
+    fn allocation_count() -> usize {
+        ALLOCATIONS.with(|a| a.get())
+    }
+
+    /// Runs every solver that frees its SuperLU structures on return once on
+    /// a small system.
+    fn solve_all_once(a_mat: &CsMat<f64>, b_mat: &Vec<Array1<f64>>, options: &mut Options) {
+        drop(SuperMatrix::from_csc_mat(a_mat.clone()));
+        drop(SuperMatrix::from_csr_mat(a_mat.clone()));
+        solve_super_lu_view(a_mat.view(), b_mat, options).unwrap();
+        // Without a timeout the solve stays on this thread, so the counter
+        // sees the matrix freed again.
+        solve_super_lu(a_mat.clone(), b_mat, None, options).unwrap();
+        solve_expert(a_mat.clone(), b_mat, None, options).unwrap();
+        let mut lu = LuFactorization::new(a_mat.clone(), options).unwrap();
+        lu.solve(b_mat).unwrap();
+        lu.refactor(a_mat).unwrap();
+        lu.solve(b_mat).unwrap();
+    }
+
+    fn leak_check_system() -> (CsMat<f64>, Vec<Array1<f64>>) {
+        (example_matrix(), vec![arr1(&[1., 1., 1., 1., 1.])])
+    }
+
+    /// Checks the buffers allocated through Rust, which SuperMatrix hands to
+    /// SuperLU. What SuperLU allocates itself is checked below.
+    #[test]
+    fn test_repeated_solves_do_not_leak() {
+        let (a_mat, b_mat) = leak_check_system();
+        let mut options = Options::default();
+
+        solve_all_once(&a_mat, &b_mat, &mut options);
+        let before = allocated_bytes();
+        for _ in 0..100 {
+            solve_all_once(&a_mat, &b_mat, &mut options);
+        }
+        assert_eq!(allocated_bytes(), before);
+    }
+
+    /// Checks the whole `malloc` heap, including SuperLU's own allocations
+    /// such as the factors and its work arrays. The solves run in a forked
+    /// child, where no other test thread allocates meanwhile. Freed chunks
+    /// kept in the allocator's caches still count as in use, so the heap
+    /// drifts by a few chunks; a leak grows it by at least a chunk per round.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    fn test_repeated_solves_do_not_leak_in_c() {
+        let (a_mat, b_mat) = leak_check_system();
+        let mut options = Options::default();
+        let in_use = || {
+            let info = unsafe { libc::mallinfo2() };
+            info.uordblks + info.hblkhd
+        };
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let leaked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                solve_all_once(&a_mat, &b_mat, &mut options);
+                let before = in_use();
+                let rounds = 1000;
+                for _ in 0..rounds {
+                    solve_all_once(&a_mat, &b_mat, &mut options);
+                }
+                in_use().saturating_sub(before) >= rounds
+            }));
+            unsafe { libc::_exit(if matches!(leaked, Ok(false)) { 0 } else { 1 }) }
+        }
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+    }
+
+    #[test]
+    fn test_typed_comp_col_matrix() {
+        let mut tri_mat = TriMat::new((3, 2));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(2, 0, 2.0);
+        tri_mat.add_triplet(1, 1, 3.0);
+        let super_matrix = SuperMatrix::from_csc_mat(tri_mat.to_csc::<usize>());
+
+        let SuperMatrix::CompCol(mat) = &super_matrix else {
+            panic!("expected SLU_NC");
+        };
+        assert_eq!((mat.nrows(), mat.ncols(), mat.nnz()), (3, 2, 3));
+        assert_eq!(mat.colptr(), &[0, 2, 3]);
+        assert_eq!(mat.rowind(), &[0, 2, 1]);
+        assert_eq!(mat.nzval::<f64>(), Some(&[1.0, 2.0, 3.0][..]));
+        assert_eq!(mat.nzval::<f32>(), None);
+
+        let SuperMatrix::CompRow(mat) = SuperMatrix::from_csr_mat(tri_mat.to_csr::<usize>()) else {
+            panic!("expected SLU_NR");
+        };
+        assert_eq!(mat.rowptr(), &[0, 1, 2, 3]);
+        assert_eq!(mat.colind(), &[0, 1, 0]);
+        assert_eq!(mat.nzval::<f64>(), Some(&[1.0, 3.0, 2.0][..]));
+    }
+
+    #[test]
+    fn test_typed_dense_matrix() {
+        let array = arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let mut mat = DenseMatrix::from_ndarray(array.clone());
+        assert_eq!((mat.nrows(), mat.ncols(), mat.lda()), (2, 3, 2));
+        assert_eq!(
+            mat.values::<f64>(),
+            Some(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0][..])
+        );
+        mat.values_mut::<f64>().unwrap()[1] = 7.0;
+        assert_eq!(
+            mat.to_ndarray::<f64>(),
+            Some(arr2(&[[1.0, 2.0, 3.0], [7.0, 5.0, 6.0]]))
+        );
+        assert!(mat.values_mut::<Complex64>().is_none());
+
+        // Only the first `nrow` entries of each column belong to the matrix.
+        let mut padded = [1.0, 2.0, -1.0, 3.0, 4.0, -1.0];
+        let mut raw: ffi::SuperMatrix = unsafe { mem::zeroed() };
+        let mat = unsafe {
+            ffi::dCreate_Dense_Matrix(
+                &mut raw,
+                2,
+                2,
+                padded.as_mut_ptr(),
+                3,
+                Stype_t::SLU_DN,
+                Dtype_t::SLU_D,
+                Mtype_t::SLU_GE,
+            );
+            let mat = SuperMatrix::from_raw(raw);
+            assert!(matches!(mat, SuperMatrix::Dense(_)));
+            mat
+        };
+        assert_eq!(
+            mat.into_ndarray::<f64>(),
+            Some(arr2(&[[1.0, 3.0], [2.0, 4.0]]))
+        );
+        unsafe { ffi::Destroy_SuperMatrix_Store(&mut raw) };
+    }
+
+    #[test]
+    fn test_factorization_typed_factors() {
+        let a_mat = example_matrix();
+        let lu = LuFactorization::new(a_mat, &Options::default()).unwrap();
+
+        let l_factor = lu.l_factor().unwrap();
+        assert!(matches!(l_factor.raw().Stype, Stype_t::SLU_SC));
+        assert_eq!(l_factor.ncols(), 5);
+        let supernodes = l_factor.supernode_count();
+        assert_eq!(l_factor.sup_to_col().len(), supernodes + 1);
+        assert_eq!(l_factor.sup_to_col()[supernodes], 5);
+        assert!(l_factor
+            .col_to_sup()
+            .iter()
+            .all(|&s| (s as usize) < supernodes));
+        assert_eq!(
+            l_factor.nzval::<f64>().unwrap().len(),
+            l_factor.nzval_colptr()[5] as usize
+        );
+
+        let u_factor = lu.u_factor().unwrap();
+        assert!(matches!(u_factor.raw().Stype, Stype_t::SLU_NC));
+        assert_eq!(u_factor.colptr().len(), 6);
+        assert_eq!(u_factor.rowind().len(), u_factor.nnz());
+    }
+
+    fn assert_lu_identity(a: &CsMat<f64>, lu: &LuFactorization) {
+        let l = lu.l_matrix().unwrap();
+        let u = lu.u_matrix().unwrap();
+        for (&value, (row, col)) in l.iter() {
+            assert!(row > col || (row == col && value == 1.0));
+        }
+        assert!(u.iter().all(|(_, (row, col))| row <= col));
+
+        let (pr, pc) = (lu.row_permutation(), lu.col_permutation());
+        let permuted = sprs::transform_mat_paq(a.view(), pr.view(), pc.view()).to_dense();
+        let product = (&l * &u).to_dense();
+        for (x, y) in permuted.iter().zip(product.iter()) {
+            assert!((x - y).abs() < 1e-10, "{permuted} != {product}");
+        }
+    }
+
+    #[test]
+    fn test_factorization_lu_identity() {
+        let a = example_matrix();
+        let lu = LuFactorization::new(a.clone(), &Options::default()).unwrap();
+        assert_lu_identity(&a, &lu);
+
+        // Small diagonal entries force row interchanges, and the dense
+        // trailing block forms supernodes.
+        let size = 12;
+        let mut tri_mat = TriMat::new((size, size));
+        let mut seed = 7u32;
+        for i in 0..size {
+            for j in 0..size {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                if i == j {
+                    tri_mat.add_triplet(i, j, 1e-3);
+                } else if i >= size / 2 && j >= size / 2 || seed % 3 == 1 {
+                    tri_mat.add_triplet(i, j, (seed >> 16) as f64 / 65536.0 + 0.5);
+                }
+            }
+        }
+        let a = tri_mat.to_csc();
+        let lu = LuFactorization::new(a.clone(), &Options::default()).unwrap();
+        assert!(lu
+            .perm_r()
+            .iter()
+            .enumerate()
+            .any(|(i, &p)| p as usize != i));
+        assert!(lu.l_factor().unwrap().supernode_count() < size);
+        assert_lu_identity(&a, &lu);
+    }
+
+    #[test]
+    fn test_factorization_determinant() {
+        let a = CsMat::csc_from_dense(arr2(&[[0., 2., 0.], [1., 0., 3.], [4., 0., 5.]]).view(), 0.);
+        let lu: LuFactorization = LuFactorization::new(a, &Options::default()).unwrap();
+        assert!((lu.determinant().unwrap() - 14.).abs() < 1e-12);
+        assert_eq!(lu.sign().unwrap(), 1.);
+        assert!((lu.log_abs_determinant().unwrap() - 14f64.ln()).abs() < 1e-12);
+
+        let a = CsMat::csc_from_dense(arr2(&[[2., 0., 0.], [0., 0., 3.], [0., 1., 0.]]).view(), 0.);
+        let lu: LuFactorization = LuFactorization::new(a, &Options::default()).unwrap();
+        assert!((lu.determinant().unwrap() + 6.).abs() < 1e-12);
+        assert_eq!(lu.sign().unwrap(), -1.);
+
+        let lu = LuFactorization::new(complex_matrix(), &Options::default()).unwrap();
+        assert!((lu.determinant().unwrap() - Complex64::new(4., 2.)).norm() < 1e-12);
+        assert!((lu.sign().unwrap().norm() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_factorization_log_determinant_no_overflow() {
+        let size = 1000;
+        let mut tri_mat = TriMat::new((size, size));
+        for i in 0..size {
+            tri_mat.add_triplet(i, i, if i % 2 == 0 { 1e10 } else { -1e10 });
+        }
+        let lu: LuFactorization =
+            LuFactorization::new(tri_mat.to_csc(), &Options::default()).unwrap();
+        assert!(lu.determinant().unwrap().is_infinite());
+        let expected = size as f64 * 1e10f64.ln();
+        assert!((lu.log_abs_determinant().unwrap() - expected).abs() < 1e-9 * expected);
+        // 500 negative pivots.
+        assert_eq!(lu.sign().unwrap(), 1.);
+    }
+
+    fn laplacian_2d(k: usize) -> CsMat<f64> {
+        let mut tri_mat = TriMat::new((k * k, k * k));
+        for i in 0..k {
+            for j in 0..k {
+                let row = i * k + j;
+                tri_mat.add_triplet(row, row, 4.0);
+                if i > 0 {
+                    tri_mat.add_triplet(row, row - k, -1.0);
+                }
+                if i + 1 < k {
+                    tri_mat.add_triplet(row, row + k, -1.0);
+                }
+                if j > 0 {
+                    tri_mat.add_triplet(row, row - 1, -1.0);
+                }
+                if j + 1 < k {
+                    tri_mat.add_triplet(row, row + 1, -1.0);
+                }
+            }
+        }
+        tri_mat.to_csc()
+    }
+
+    #[test]
+    fn test_incomplete_lu_without_dropping() {
+        let a_mat = example_matrix();
+        let options = Options::builder().drop_tolerance(0.0).build().unwrap();
+        let ilu = IncompleteLu::new(a_mat.clone(), &options).unwrap();
+        assert_eq!(ilu.zero_pivots(), 0);
+
+        let z = ilu.apply(&arr1(&[1., 1., 1., 1., 1.])).unwrap();
+        let expected = arr1(&[-0.03125, 0.06547619, 0.01339286, 0.0625, 0.03273810]);
+        assert!(array1s_close(&z, &expected, 1e-4));
+        assert!(ilu.apply(&arr1(&[1., 1.])).is_err());
+
+        // `Equil`, on by default, does not change the factors.
+        let options = Options::builder()
+            .drop_tolerance(0.0)
+            .equilibrate(false)
+            .build()
+            .unwrap();
+        let unscaled = IncompleteLu::new(a_mat, &options).unwrap();
+        assert_eq!(unscaled.apply(&arr1(&[1., 1., 1., 1., 1.])).unwrap(), z);
+    }
+
+    #[test]
+    fn test_incomplete_lu_preconditions() {
+        let a_mat = laplacian_2d(20);
+        let options = Options::builder()
+            .drop_tolerance(1e-2)
+            .milu(Milu::Smilu2)
+            .build()
+            .unwrap();
+        let ilu = IncompleteLu::new(a_mat.clone(), &options).unwrap();
+        let lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+        let lu_nnz = lu.l_factor().unwrap().nnz() + lu.u_factor().unwrap().nnz();
+        assert!(ilu.nnz() < lu_nnz);
+
+        // `A z` approximates `r` far better than `r` itself does.
+        let r = Array1::from_shape_fn(400, |i| (i % 7) as f64 - 3.0);
+        let z = ilu.apply(&r).unwrap();
+        let residual = &r - &(&a_mat * &z);
+        let norm = |v: &Array1<f64>| v.dot(v).sqrt();
+        assert!(norm(&residual) < 0.5 * norm(&r));
+    }
+
+    #[test]
+    fn test_options_builder_ilu() {
+        let options = Options::default();
+        assert_eq!(options.ffi.ILU_DropTol, 1e-4);
+        assert_eq!(options.ffi.ILU_FillFactor, 10.0);
+
+        let options = Options::builder()
+            .drop_tolerance(1e-3)
+            .fill_factor(5.0)
+            .milu(Milu::Smilu1)
+            .drop_rule(DropRule::BASIC | DropRule::COLUMN | DropRule::DYNAMIC)
+            .build()
+            .unwrap();
+        assert_eq!(options.ffi.ILU_DropTol, 1e-3);
+        assert_eq!(options.ffi.ILU_FillFactor, 5.0);
+        assert!(matches!(options.ffi.ILU_MILU, ffi::milu_t::SMILU_1));
+        assert_eq!(options.ffi.ILU_DropRule, 0x0001 | 0x0004 | 0x0010);
+
+        let res = Options::builder().drop_tolerance(-1.0).build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidDropTolerance(-1.0)));
+        let res = Options::builder().fill_factor(0.5).build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidFillFactor(0.5)));
+        let res = Options::builder()
+            .drop_rule(DropRule::PROWS | DropRule::AREA)
+            .build();
+        assert_eq!(res.err(), Some(OptionsError::InvalidDropRule));
+    }
+
+    fn assert_krylov_solution(a: &CsMat<f64>, b: &Array1<f64>, report: &KrylovReport) {
+        assert_eq!(report.reason, ConvergenceReason::Converged);
+        assert_eq!(report.residual_history.len(), report.iterations + 1);
+        let residual = b - &(a * &report.solution);
+        assert!(residual.dot(&residual).sqrt() <= 1e-6 * b.dot(b).sqrt());
+    }
+
+    #[test]
+    fn test_gmres() {
+        let a_mat = laplacian_2d(20);
+        let b = Array1::from_shape_fn(400, |i| (i % 7) as f64 - 3.0);
+        let options = KrylovOptions::default();
+
+        let plain = gmres(&a_mat, &b, None, &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &plain);
+        assert!(plain.iterations > options.restart);
+
+        let ilu = IncompleteLu::new(a_mat.clone(), &Options::default()).unwrap();
+        let preconditioned = gmres(&a_mat, &b, Some(&ilu), &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &preconditioned);
+        assert!(preconditioned.iterations < plain.iterations);
+
+        let lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+        let exact = gmres(&a_mat, &b, Some(&lu), &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &exact);
+        assert!(exact.iterations <= 2);
+    }
+
+    #[test]
+    fn test_gmres_nonsymmetric() {
+        let a_mat = example_matrix();
+        let b = arr1(&[1., 1., 1., 1., 1.]);
+        let options = KrylovOptions {
+            restart: 2,
+            ..KrylovOptions::default()
+        };
+        let report = gmres(&a_mat, &b, None, &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &report);
+        let expected = arr1(&[-0.03125, 0.06547619, 0.01339286, 0.0625, 0.03273810]);
+        assert!(array1s_close(&report.solution, &expected, 1e-4));
+    }
+
+    #[test]
+    fn test_bicgstab() {
+        let a_mat = laplacian_2d(20);
+        let b = Array1::from_shape_fn(400, |i| (i % 7) as f64 - 3.0);
+        let options = KrylovOptions::default();
+
+        let plain = bicgstab(&a_mat, &b, None, &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &plain);
+
+        let ilu = IncompleteLu::new(a_mat.clone(), &Options::default()).unwrap();
+        let preconditioned = bicgstab(&a_mat, &b, Some(&ilu), &options).unwrap();
+        assert_krylov_solution(&a_mat, &b, &preconditioned);
+        assert!(preconditioned.iterations < plain.iterations);
+    }
+
+    #[test]
+    fn test_krylov_limits() {
+        let a_mat = laplacian_2d(20);
+        let b = Array1::from_shape_fn(400, |i| (i % 7) as f64 - 3.0);
+        let options = KrylovOptions {
+            max_iterations: 5,
+            ..KrylovOptions::default()
+        };
+        for report in [
+            gmres(&a_mat, &b, None, &options).unwrap(),
+            bicgstab(&a_mat, &b, None, &options).unwrap(),
+        ] {
+            assert_eq!(report.reason, ConvergenceReason::MaxIterations);
+            assert_eq!(report.iterations, 5);
+            assert_eq!(report.residual_history.len(), 6);
+            assert!(report.residual_history[5] < report.residual_history[0]);
+        }
+
+        let zero = gmres(&a_mat, &Array1::zeros(400), None, &options).unwrap();
+        assert_eq!(zero.reason, ConvergenceReason::Converged);
+        assert_eq!(zero.iterations, 0);
+
+        let res = bicgstab(&a_mat, &Array1::zeros(3), None, &options);
+        assert_eq!(
+            res.err(),
+            Some(SolverError::DimensionMismatch {
+                expected: 400,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_factorization_solve_transpose() {
+        let a_mat = example_matrix();
+        let lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+
+        let b = arr1(&[1., 2., 3., 4., 5.]);
+        let x = lu.solve_transpose(std::slice::from_ref(&b)).unwrap();
+        assert!(array1s_close(&(&a_mat.transpose_view() * &x[0]), &b, 1e-12));
+        let x_adjoint = lu.solve_adjoint(std::slice::from_ref(&b)).unwrap();
+        assert!(array1s_close(&x_adjoint[0], &x[0], 1e-12));
+        assert!(lu.solve_transpose(&[arr1(&[1.])]).is_err());
+
+        let a_mat = complex_matrix();
+        let lu = LuFactorization::new(a_mat.clone(), &Options::default()).unwrap();
+        let b = arr1(&[Complex64::new(1., 2.), Complex64::new(-1., 0.5)]);
+        let x = lu.solve_adjoint(std::slice::from_ref(&b)).unwrap();
+        let a_adjoint = a_mat.transpose_view().map(|v| v.conj());
+        let residual = &(&a_adjoint * &x[0]) - &b;
+        assert!(residual.iter().all(|r| r.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_solver_transpose_option() {
+        let a_mat = example_matrix();
+        let b = vec![arr1(&[1., 2., 3., 4., 5.])];
+        let mut options = Options::builder()
+            .transpose(Transpose::Transpose)
+            .build()
+            .unwrap();
+
+        for a in [a_mat.clone(), a_mat.to_csr()] {
+            let x = solve_super_lu(a, &b, None, &mut options).unwrap();
+            assert!(array1s_close(
+                &(&a_mat.transpose_view() * &x[0]),
+                &b[0],
+                1e-12
+            ));
+        }
+        let x = solve_super_lu_view(a_mat.view(), &b, &mut options).unwrap();
+        assert!(array1s_close(
+            &(&a_mat.transpose_view() * &x[0]),
+            &b[0],
+            1e-12
+        ));
+
+        let mut options = Options::builder()
+            .transpose(Transpose::ConjugateTranspose)
+            .build()
+            .unwrap();
+        let a_mat = complex_matrix();
+        let a_adjoint = a_mat.transpose_view().map(|v| v.conj());
+        let b = vec![arr1(&[Complex64::new(1., 2.), Complex64::new(-1., 0.5)])];
+        let x = solve_super_lu(a_mat.clone(), &b, None, &mut options).unwrap();
+        assert!((&(&a_adjoint * &x[0]) - &b[0])
+            .iter()
+            .all(|r| r.norm() < 1e-12));
+        let x = solve_super_lu_view(a_mat.view(), &b, &mut options).unwrap();
+        assert!((&(&a_adjoint * &x[0]) - &b[0])
+            .iter()
+            .all(|r| r.norm() < 1e-12));
+        for a in [a_mat.clone(), a_mat.to_csr()] {
+            let report = solve_expert(a, &b, None, &mut options).unwrap();
+            assert!((&(&a_adjoint * &report.solution[0]) - &b[0])
+                .iter()
+                .all(|r| r.norm() < 1e-12));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_solver_in_process() {
+        let a_mat = example_matrix();
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.]), arr1(&[2., 2., 2., 2., 2.])];
+        let mut options = Options::default();
+        let res =
+            solve_super_lu_in_process(a_mat, &b_mat, Some(Duration::from_secs(5)), &mut options)
+                .unwrap();
+
+        let expected = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+        assert!(array1s_close(&res[0], &expected, 1e-12));
+        assert!(array1s_close(&res[1], &(expected * 2.), 1e-12));
+
+        let b_mat = vec![arr1(&[Complex64::new(1., 3.), Complex64::new(1., 3.)])];
+        let res = solve_super_lu_in_process(complex_matrix(), &b_mat, None, &mut options).unwrap();
+        let expected = [Complex64::new(1., 0.), Complex64::new(0., 1.)];
+        for (x, e) in res[0].iter().zip(expected.iter()) {
+            assert!((x - e).norm() < 1e-12);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_solver_in_process_errors() {
+        let mut tri_mat = TriMat::new((3, 3));
+        tri_mat.add_triplet(0, 0, 1.0);
+        tri_mat.add_triplet(0, 1, 1.0);
+        tri_mat.add_triplet(1, 0, 1.0);
+        tri_mat.add_triplet(1, 1, 1.0);
+        tri_mat.add_triplet(2, 2, 1.0);
+        let b_mat = vec![arr1(&[1., 1., 1.])];
+        let mut options = Options::default();
+        let res = solve_super_lu_in_process(tri_mat.to_csc(), &b_mat, None, &mut options);
+        assert!(matches!(res, Err(SolverError::Singular { .. })));
+
+        let b_mat = vec![Array1::ones(200 * 200)];
+        let start = std::time::Instant::now();
+        let res = solve_super_lu_in_process(
+            laplacian_2d(200),
+            &b_mat,
+            Some(Duration::from_nanos(1)),
+            &mut options,
+        );
+        assert_eq!(res, Err(SolverError::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_child_reaped();
+    }
+
+    /// Checks that the last child forked by this thread is gone, neither
+    /// running nor left as a zombie.
+    #[cfg(unix)]
+    fn assert_child_reaped() {
+        let pid = crate::process::LAST_CHILD
+            .with(|child| child.get())
+            .unwrap();
+        let alive = unsafe { libc::kill(pid, 0) } == 0;
+        assert!(!alive);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::ESRCH)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_solver_in_process_overlapping() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        // Fast solves keep forking while slow children run. A slow child
+        // that inherits the pipe of a fast solve must not hold up its result.
+        let slow_running = Arc::new(AtomicBool::new(true));
+        let fast: Vec<_> = (0..4)
+            .map(|_| {
+                let slow_running = Arc::clone(&slow_running);
+                std::thread::spawn(move || {
+                    let a_mat = laplacian_2d(20);
+                    let b_mat = vec![Array1::<f64>::ones(20 * 20)];
+                    let mut slowest = Duration::ZERO;
+                    while slow_running.load(Ordering::Relaxed) {
+                        let start = std::time::Instant::now();
+                        let x = solve_super_lu_in_process(
+                            a_mat.clone(),
+                            &b_mat,
+                            None,
+                            &mut Options::default(),
+                        )
+                        .unwrap();
+                        slowest = slowest.max(start.elapsed());
+                        assert_child_reaped();
+                        let residual = &b_mat[0] - &(&a_mat * &x[0]);
+                        assert!(residual.iter().all(|r| r.abs() < 1e-10));
+                    }
+                    slowest
+                })
+            })
+            .collect();
+
+        let slow: Vec<_> = (0..4)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(50 * i));
+                    let mut options = OptionsBuilder::new()
+                        .column_permutation(ColumnPermutation::Natural)
+                        .build()
+                        .unwrap();
+                    let b_mat = vec![Array1::ones(250 * 250)];
+                    let res = solve_super_lu_in_process(
+                        laplacian_2d(250),
+                        &b_mat,
+                        Some(Duration::from_secs(2)),
+                        &mut options,
+                    );
+                    assert_child_reaped();
+                    res
+                })
+            })
+            .collect();
+
+        for slow in slow {
+            assert_eq!(slow.join().unwrap(), Err(SolverError::Timeout));
+        }
+        slow_running.store(false, Ordering::Relaxed);
+        for fast in fast {
+            let slowest = fast.join().unwrap();
+            assert!(slowest < Duration::from_secs(1), "{:?}", slowest);
+        }
+    }
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Waker};
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            std::thread::park();
+        }
+    }
+
+    #[test]
+    fn test_solver_async() {
+        let a_mat = example_matrix();
+        let b_mat = vec![arr1(&[1., 1., 1., 1., 1.])];
+        let mut options = Options::default();
+
+        let futures: Vec<_> = (0..8)
+            .map(|_| solve_super_lu_async(a_mat.clone(), b_mat.clone(), &mut options))
+            .collect();
+        let expected = arr1(&[
+            -0.03125000000000001,
+            0.06547619047619048,
+            0.013392857142857147,
+            0.0625,
+            0.03273809523809524,
+        ]);
+        for future in futures {
+            let res = block_on(future).unwrap();
+            assert!(array1s_close(&res[0], &expected, 1e-12));
+        }
+
+        let b_mat = vec![arr1(&[Complex64::new(1., 3.), Complex64::new(1., 3.)])];
+        let res = block_on(solve_super_lu_async(complex_matrix(), b_mat, &mut options)).unwrap();
+        assert!((res[0][1] - Complex64::new(0., 1.)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_solver_async_errors_and_cancellation() {
+        let b_mat = vec![arr1(&[1., 1.])];
+        let mut options = Options::default();
+        let res = block_on(solve_super_lu_async(
+            CsMat::<f64>::eye(3),
+            b_mat,
+            &mut options,
+        ));
+        assert_eq!(
+            res,
+            Err(SolverError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+
+        // Dropped futures leave the pool usable for the next solve.
+        let b_mat = vec![Array1::ones(100 * 100)];
+        for _ in 0..16 {
+            drop(solve_super_lu_async(
+                laplacian_2d(100),
+                b_mat.clone(),
+                &mut options,
+            ));
+        }
+        let res = block_on(solve_super_lu_async(
+            laplacian_2d(100),
+            b_mat.clone(),
+            &mut options,
+        ))
+        .unwrap();
+        let residual = &b_mat[0] - &(&laplacian_2d(100) * &res[0]);
+        assert!(residual.iter().all(|r| r.abs() < 1e-10));
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let mut options = Options::default();
+        let singular = {
+            let mut tri_mat = TriMat::new((2, 2));
+            tri_mat.add_triplet(0, 0, 1.0);
+            tri_mat.add_triplet(0, 1, 1.0);
+            tri_mat.add_triplet(1, 0, 1.0);
+            tri_mat.add_triplet(1, 1, 1.0);
+            tri_mat.to_csc()
+        };
+        let systems: Vec<(CsMat<f64>, Vec<Array1<f64>>)> = (0..40)
+            .map(|i| match i % 10 {
+                3 => (singular.clone(), vec![arr1(&[1., 2.])]),
+                7 => (CsMat::eye(3), vec![arr1(&[1., 2.])]),
+                _ => {
+                    let k = 2 + i % 5;
+                    let b = Array1::from_shape_fn(k * k, |j| (i + j) as f64);
+                    (laplacian_2d(k), vec![b.clone(), b * 2.])
+                }
+            })
+            .collect();
+
+        let results = solve_batch(systems.clone(), &mut options);
+        assert_eq!(results.len(), systems.len());
+        for ((a, b), res) in systems.iter().zip(&results) {
+            match res {
+                Ok(x) => {
+                    assert_eq!(x.len(), b.len());
+                    for (x, b) in x.iter().zip(b) {
+                        let residual = b - &(a * x);
+                        assert!(residual.iter().all(|r| r.abs() < 1e-10));
+                    }
+                }
+                Err(SolverError::Singular { .. }) => assert_eq!(a, &singular),
+                Err(e) => assert_eq!(
+                    *e,
+                    SolverError::DimensionMismatch {
+                        expected: 3,
+                        found: 2
+                    }
+                ),
+            }
+        }
+        assert_eq!(results.iter().filter(|res| res.is_err()).count(), 8);
+        assert!(solve_batch(Vec::<(CsMat<f64>, _)>::new(), &mut options).is_empty());
+    }
+
+    #[test]
+    fn test_solver_ndarray_rhs() {
+        use ndarray::ShapeBuilder;
+
+        let expected = example_solution();
+        let mut options = Options::default();
+        let x = solve_super_lu_vector(
+            example_matrix(),
+            Array1::ones(5).view(),
+            Some(Duration::from_secs(5)),
+            &mut options,
+        )
+        .unwrap();
+        assert!(array1s_close(&x, &expected, 1e-12));
+
+        let b = Array2::from_shape_fn((5, 3), |(_, col)| (col + 1) as f64);
+        let b_f = Array2::from_shape_fn((5, 3).f(), |(_, col)| (col + 1) as f64);
+        let b_reversed = b.slice(ndarray::s![..;-1, ..]);
+        for b in [b.view(), b_f.view(), b_reversed] {
+            let x = solve_super_lu_array(example_matrix(), b, None, &mut options).unwrap();
+            assert_eq!(x.dim(), (5, 3));
+            for col in 0..3 {
+                let ax = &example_matrix() * &x.column(col).to_owned();
+                assert!(array1s_close(&ax, &b.column(col).to_owned(), 1e-12));
+            }
+        }
+        let x = solve_super_lu_array(example_matrix(), b_f.view(), None, &mut options).unwrap();
+        assert!(array1s_close(
+            &x.column(2).to_owned(),
+            &(&expected * 3.),
+            1e-12
+        ));
+
+        let res = solve_super_lu_array(
+            example_matrix(),
+            Array2::ones((4, 2)).view(),
+            None,
+            &mut options,
+        );
+        assert_eq!(
+            res,
+            Err(SolverError::DimensionMismatch {
+                expected: 5,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_solver_in_place() {
+        use ndarray::{s, ShapeBuilder};
+
+        let expected = example_solution();
+        let mut options = Options::default();
+
+        // Rows 1..6 of a Fortran-ordered array, so columns are 7 apart.
+        let mut storage = Array2::from_shape_fn((7, 2).f(), |(_, col)| (col + 1) as f64);
+        let ptr: *const f64 = &storage[[1, 0]];
+        {
+            let mut b = storage.slice_mut(s![1..6, ..]);
+            assert_eq!(b.as_ptr(), ptr);
+            let b_mat = DenseMatrix::from_view_mut(b.view_mut()).unwrap();
+            assert_eq!(b_mat.lda(), 7);
+            assert_eq!(b_mat.values::<f64>().unwrap().as_ptr(), ptr);
+            drop(b_mat);
+            solve_super_lu_in_place(example_matrix(), &mut b, &mut options).unwrap();
+        }
+        assert_eq!(storage[[0, 0]], 1.);
+        assert_eq!(storage[[6, 1]], 2.);
+        assert!(array1s_close(
+            &storage.slice(s![1..6, 0]).to_owned(),
+            &expected,
+            1e-12
+        ));
+        assert!(array1s_close(
+            &storage.slice(s![1..6, 1]).to_owned(),
+            &(&expected * 2.),
+            1e-12
+        ));
+
+        let mut b = Array2::from_elem((5, 2), 1.);
+        assert!(DenseMatrix::from_view_mut(b.view_mut()).is_none());
+        solve_super_lu_in_place(example_matrix(), &mut b.view_mut(), &mut options).unwrap();
+        assert!(array1s_close(&b.column(1).to_owned(), &expected, 1e-12));
+    }
+
+    #[test]
+    fn test_factorization_solve_into() {
+        use ndarray::{s, Axis, ShapeBuilder};
+
+        let expected = example_solution();
+        let lu = LuFactorization::new(example_matrix(), &Options::default()).unwrap();
+        let mut x = Array2::zeros((5, 2).f());
+        let mut vector = Array1::zeros(5);
+
+        let before = allocation_count();
+        for _ in 0..10 {
+            x.column_mut(0).fill(1.);
+            x.column_mut(1).fill(2.);
+            lu.solve_into(x.view_mut()).unwrap();
+            vector.fill(1.);
+            lu.solve_into(vector.view_mut().insert_axis(Axis(1)))
+                .unwrap();
+        }
+        assert_eq!(allocation_count(), before);
+        assert!(array1s_close(&x.column(0).to_owned(), &expected, 1e-12));
+        assert!(array1s_close(
+            &x.column(1).to_owned(),
+            &(&expected * 2.),
+            1e-12
+        ));
+        assert!(array1s_close(&vector, &expected, 1e-12));
+
+        // Row-major right-hand sides are solved in a copy.
+        let mut x = Array2::from_elem((2, 5), 1.);
+        lu.solve_into(x.view_mut().reversed_axes()).unwrap();
+        assert!(array1s_close(&x.row(1).to_owned(), &expected, 1e-12));
+        let mut x = Array2::from_elem((5, 2), 1.);
+        lu.solve_into(x.view_mut()).unwrap();
+        assert!(array1s_close(&x.column(1).to_owned(), &expected, 1e-12));
+
+        let mut x = Array2::<f64>::zeros((6, 1));
+        assert_eq!(
+            lu.solve_into(x.slice_mut(s![.., ..])),
+            Err(SolverError::DimensionMismatch {
+                expected: 5,
+                found: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_factorization_solve_sparse() {
+        let lu = LuFactorization::new(example_matrix(), &Options::default()).unwrap();
+        let b = CsVec::new(5, vec![0, 1, 2, 3, 4], vec![1.; 5]);
+        let x = lu.solve_sparse(&b, 0.).unwrap();
+        assert_eq!(x.nnz(), 5);
+        assert!(array1s_close(&x.to_dense(), &example_solution(), 1e-12));
+        assert_eq!(
+            lu.solve_sparse(&CsVec::new(4, vec![0], vec![1.]), 0.),
+            Err(SolverError::DimensionMismatch {
+                expected: 5,
+                found: 4
+            })
+        );
+
+        // Columns of the inverse of a block diagonal matrix stay in their
+        // block, up to rounding.
+        let mut tri_mat = TriMat::new((40, 40));
+        for block in 0..20 {
+            let i = 2 * block;
+            tri_mat.add_triplet(i, i, 2.0);
+            tri_mat.add_triplet(i, i + 1, 1.0);
+            tri_mat.add_triplet(i + 1, i, 1.0);
+            tri_mat.add_triplet(i + 1, i + 1, 3.0);
+        }
+        let a = tri_mat.to_csc();
+        let lu = LuFactorization::new(a.clone(), &Options::default()).unwrap();
+        let identity: CsMat<f64> = CsMat::eye(40);
+        let inverse = lu.solve_sparse_block(&identity, 1e-14).unwrap();
+        assert!(inverse.is_csc());
+        assert_eq!(inverse.nnz(), 80);
+        let product = &a * &inverse;
+        for (value, (row, col)) in product.iter() {
+            let expected = if row == col { 1. } else { 0. };
+            assert!((value - expected).abs() < 1e-12);
+        }
+
+        let columns = identity.to_csr();
+        let dropped = lu.solve_sparse_block(&columns, 0.3).unwrap();
+        assert_eq!(dropped.cols(), 40);
+        assert_eq!(dropped.nnz(), 40);
+        assert!(dropped.iter().all(|(value, _)| value.abs() > 0.3));
+
+        // Non-finite right-hand sides show up in the solution whatever the
+        // drop tolerance.
+        for bad in [f64::NAN, f64::INFINITY] {
+            let b = CsVec::new(40, vec![0, 7], vec![bad, 1.]);
+            let x = lu.solve_sparse(&b, f64::MAX).unwrap();
+            assert!(x.nnz() > 0);
+            assert!(x.iter().all(|(_, value)| !value.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_structural_check_long_augmenting_path() {
+        use crate::pattern::structurally_singular_column;
+
+        // Column `j` holds rows `j` and `j + 1`, the last column only row 0.
+        // Matching the last column shifts every other column by one row.
+        let n = 200_000;
+        let mut tri_mat = TriMat::new((n, n));
+        for j in 0..n - 1 {
+            tri_mat.add_triplet(j + 1, j, 1.0);
+        }
+        tri_mat.add_triplet(0, n - 1, 1.0);
+        let shifted: CsMat<f64> = tri_mat.to_csc();
+        for j in 0..n - 1 {
+            tri_mat.add_triplet(j, j, 2.0);
+        }
+        let a_mat: CsMat<f64> = tri_mat.to_csc();
+        assert_eq!(structurally_singular_column(a_mat.view()), None);
+        assert_eq!(structurally_singular_column(shifted.view()), None);
+        assert_eq!(structurally_singular_column(a_mat.to_csr().view()), None);
+
+        // The last two columns only hold row `n - 2`.
+        let mut tri_mat = TriMat::new((n, n));
+        for j in 0..n - 1 {
+            tri_mat.add_triplet(j, j, 2.0);
+            if j + 2 < n {
+                tri_mat.add_triplet(j + 1, j, 1.0);
+            }
+        }
+        tri_mat.add_triplet(n - 2, n - 1, 1.0);
+        let singular: CsMat<f64> = tri_mat.to_csc();
+        assert_eq!(structurally_singular_column(singular.view()), Some(n - 1));
+    }
+
+    /// Held by tests that occupy every pool worker, which would deadlock if
+    /// two of them ran at once.
+    static WHOLE_POOL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn test_solver_async_dropped_before_start() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::{mpsc, Arc, Barrier};
+        use std::time::Duration;
+
+        let _pool = WHOLE_POOL.lock().unwrap();
+        // Keeps every worker busy until the future has been dropped.
+        let workers = crate::pool::worker_count();
+        let started = Arc::new(Barrier::new(workers + 1));
+        let release = Arc::new(Barrier::new(workers + 1));
+        for _ in 0..workers {
+            let started = Arc::clone(&started);
+            let release = Arc::clone(&release);
+            crate::pool::spawn(move || {
+                started.wait();
+                release.wait();
+            });
+        }
+        started.wait();
+
+        struct Discarded(mpsc::Sender<()>);
+        impl Drop for Discarded {
+            fn drop(&mut self) {
+                let _ = self.0.send(());
+            }
+        }
+        let ran = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let future = {
+            let ran = Arc::clone(&ran);
+            let discarded = Discarded(sender);
+            crate::future::spawn::<f64>(move || {
+                let _discarded = discarded;
+                ran.store(true, Ordering::SeqCst);
+                Ok(Vec::new())
+            })
+        };
+        drop(future);
+        release.wait();
+
+        // The task is dropped once a worker has taken it off the queue.
+        receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_solve_batch_from_pool_workers() {
+        use std::sync::{mpsc, Arc, Barrier};
+        use std::time::Duration;
+
+        let _pool = WHOLE_POOL.lock().unwrap();
+        // Every worker calls solve_batch at once, so none is left to run
+        // jobs that the batches would wait for.
+        let workers = crate::pool::worker_count();
+        let started = Arc::new(Barrier::new(workers));
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let started = Arc::clone(&started);
+            let sender = sender.clone();
+            crate::pool::spawn(move || {
+                started.wait();
+                let systems = (2..6).map(|k| (laplacian_2d(k), vec![Array1::ones(k * k)]));
+                let _ = sender.send(solve_batch(systems, &mut Options::default()));
+            });
+        }
+
+        for _ in 0..workers {
+            let results = receiver.recv_timeout(Duration::from_secs(30)).unwrap();
+            for (k, res) in (2..6).zip(results) {
+                let x = res.unwrap();
+                let residual = Array1::ones(k * k) - &laplacian_2d(k) * &x[0];
+                assert!(residual.iter().all(|r: &f64| r.abs() < 1e-10));
+            }
+        }
+    }
 }